@@ -0,0 +1,86 @@
+// Seabed splatting for `FloorMaterial`: each chunk vertex carries up to four
+// region weights (vertex colour) and its slope (uv_b.x). Every region slot
+// has a flat and a steep layer in the floor texture array; they're blended by
+// slope, then the slots by weight.
+
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::alpha_discard,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct FloorSplat {
+    flat_layer: vec4<u32>,
+    steep_layer: vec4<u32>,
+    flat_tint: array<vec4<f32>, 4>,
+    steep_tint: array<vec4<f32>, 4>,
+    flat_scale: vec4<f32>,
+    steep_scale: vec4<f32>,
+    steep_start: f32,
+    steep_end: f32,
+}
+
+@group(2) @binding(100) var<uniform> floor_splat: FloorSplat;
+@group(2) @binding(101) var floor_layers: texture_2d_array<f32>;
+@group(2) @binding(102) var floor_sampler: sampler;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+#ifdef VERTEX_COLORS
+    let weights = in.color;
+#else
+    let weights = vec4(1.0, 0.0, 0.0, 0.0);
+#endif
+#ifdef VERTEX_UVS_B
+    let slope = in.uv_b.x;
+#else
+    let slope = 1.0 - normalize(in.world_normal).y;
+#endif
+    let steep = smoothstep(floor_splat.steep_start, floor_splat.steep_end, slope);
+    let world = in.world_position.xz;
+
+    // sample every slot regardless of weight: textureSample needs uniform control flow
+    var colour = vec4(0.0);
+    for (var i = 0u; i < 4u; i++) {
+        let flat_colour = textureSample(
+            floor_layers,
+            floor_sampler,
+            world / floor_splat.flat_scale[i],
+            floor_splat.flat_layer[i],
+        ) * floor_splat.flat_tint[i];
+        let steep_colour = textureSample(
+            floor_layers,
+            floor_sampler,
+            world / floor_splat.steep_scale[i],
+            floor_splat.steep_layer[i],
+        ) * floor_splat.steep_tint[i];
+        colour += weights[i] * mix(flat_colour, steep_colour, steep);
+    }
+    pbr_input.material.base_color = vec4(colour.rgb, 1.0);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+#endif
+    return out;
+}
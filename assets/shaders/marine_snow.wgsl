@@ -0,0 +1,75 @@
+// Marine snow for `MarineSnowMaterial`: every speck is a quad whose four
+// corners sit at the same spot in a cube of water (position). Here the spot
+// is carried along by the current, wrapped into the cube around the camera
+// and the quad turned to face it. uv_0 is the corner, uv_1 two random
+// numbers for the speck: x decides whether it shows at the current density,
+// y its size.
+
+#import bevy_pbr::mesh_view_bindings::{view, globals}
+
+struct MarineSnow {
+    colour: vec4<f32>,
+    drift: vec3<f32>,
+    volume: f32,
+    density: f32,
+    size: f32,
+}
+
+@group(2) @binding(0) var<uniform> snow: MarineSnow;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) corner: vec2<f32>,
+    @location(2) random: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) alpha: f32,
+}
+
+@vertex
+fn vertex(in: Vertex) -> VertexOutput {
+    var out: VertexOutput;
+    out.corner = in.corner;
+
+    // past the density: fold the quad to a point so nothing draws
+    if in.random.x >= snow.density {
+        out.clip_position = vec4(0.0, 0.0, 0.0, 1.0);
+        out.alpha = 0.0;
+        return out;
+    }
+
+    // a little tumble of its own on top of the current
+    let t = globals.time * 0.4 + in.random.x * 100.0;
+    let tumble = vec3(sin(t), 0.5 * sin(t * 1.3 + 1.7), cos(t * 0.8)) * 0.05;
+
+    let offset = in.position + snow.drift + tumble - view.world_position;
+    let wrapped = (fract(offset / snow.volume + 0.5) - 0.5) * snow.volume;
+    let centre = view.world_position + wrapped;
+
+    let right = view.world_from_view[0].xyz;
+    let up = view.world_from_view[1].xyz;
+    let size = snow.size * (0.5 + in.random.y);
+    let world = centre + (right * in.corner.x + up * in.corner.y) * size;
+    out.clip_position = view.clip_from_world * vec4(world, 1.0);
+
+    // fade out well inside the cube, so nothing pops as it wraps, and right
+    // up close, where a speck would fill the screen
+    let distance = length(wrapped);
+    out.alpha = snow.colour.a
+        * (1.0 - smoothstep(0.3, 0.5, distance / snow.volume))
+        * smoothstep(0.1, 0.5, distance);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // soft round speck
+    let falloff = 1.0 - dot(in.corner, in.corner);
+    if falloff <= 0.0 {
+        discard;
+    }
+    return vec4(snow.colour.rgb, in.alpha * falloff * falloff);
+}
@@ -0,0 +1,436 @@
+use crate::camera::components::FollowTarget;
+use crate::fishy::{fish_movement_system, steered_movement_system, SteeringTarget, WATER_SURFACE};
+use crate::reef_health::CoralHealth;
+use crate::region_sampler::{BehaviourKind, RegionSampler};
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::f32::consts::TAU;
+use std::ops::Range;
+
+/// How far off an animal notices coral worth grazing on.
+const GRAZE_REACH: f32 = 6.0;
+/// How high over the seabed, or a coral, an animal settles to rest or graze.
+const SETTLE_HEIGHT: f32 = 0.3;
+/// Open water a cruising animal keeps under the surface.
+const SURFACE_CLEARANCE: f32 = 0.5;
+/// How far ahead of the player a fleeing animal aims.
+const FLEE_REACH: f32 = 4.0;
+/// Flat out: the movement caps it at the animal's sprint multiplier.
+const FLEE_PACE: f32 = 2.0;
+
+/// Wandering, grazing, resting and fleeing for animals other than the
+/// player. Each decides what to do in `Behaviour::step`, from plain numbers
+/// and whatever `Surroundings` it's given, then swims there through a
+/// `SteeringTarget` with the player's own physics. Random choices come from
+/// `BehaviourRng`; insert a seeded one for repeatable runs.
+///
+/// Call `.add_plugins(BehaviourPlugin)` in your App.
+pub struct BehaviourPlugin;
+
+impl Plugin for BehaviourPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BehaviourRng>().add_systems(
+            // on the same fixed tick as the player's movement (and so the
+            // camera following it), so they don't judder against each other
+            FixedUpdate,
+            (behaviour_system, steered_movement_system)
+                .chain()
+                .after(fish_movement_system)
+                .run_if(resource_exists::<RegionSampler>),
+        );
+    }
+}
+
+/// Where animals' random choices come from.
+#[derive(Resource)]
+pub struct BehaviourRng(pub ChaCha8Rng);
+
+impl BehaviourRng {
+    pub fn seeded(seed: u64) -> Self {
+        BehaviourRng(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl Default for BehaviourRng {
+    fn default() -> Self {
+        BehaviourRng::seeded(rand::rng().random())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BehaviourState {
+    /// Cruising between spots around home.
+    Wander,
+    /// Nosing at a coral.
+    Graze,
+    /// Settled on the seabed.
+    Rest,
+    /// Getting away from the player.
+    Flee,
+}
+
+/// How an animal goes about its day.
+#[derive(Clone, Debug)]
+pub struct Traits {
+    /// How far from home it wanders.
+    pub roam: f32,
+    /// Height over the seabed it cruises at.
+    pub cruise_height: Range<f32>,
+    /// Share of its top speed when in no hurry.
+    pub pace: f32,
+    /// Seconds it heads for one spot before deciding what to do next.
+    pub wander_time: Range<f32>,
+    /// Chance, after wandering, of grazing on a coral, if there's one near.
+    pub graze_chance: f32,
+    pub graze_time: Range<f32>,
+    /// Chance, after wandering (and not grazing), of resting on the seabed.
+    pub rest_chance: f32,
+    pub rest_time: Range<f32>,
+    /// It flees when the player comes this close (0 never), until they're
+    /// `calm_distance` away.
+    pub flee_distance: f32,
+    pub calm_distance: f32,
+}
+
+impl Traits {
+    pub fn of(kind: BehaviourKind) -> Traits {
+        match kind {
+            // unbothered, always after a bite of coral
+            BehaviourKind::Turtle => Traits {
+                roam: 10.0,
+                cruise_height: 0.8..3.0,
+                pace: 0.7,
+                wander_time: 8.0..20.0,
+                graze_chance: 0.6,
+                graze_time: 10.0..25.0,
+                rest_chance: 0.3,
+                rest_time: 15.0..40.0,
+                flee_distance: 1.5,
+                calm_distance: 4.0,
+            },
+            // gliding low over the sand, and a long time settled on it
+            BehaviourKind::Ray => Traits {
+                roam: 12.0,
+                cruise_height: 0.3..1.0,
+                pace: 0.8,
+                wander_time: 6.0..15.0,
+                graze_chance: 0.0,
+                graze_time: 0.0..0.0,
+                rest_chance: 0.5,
+                rest_time: 20.0..60.0,
+                flee_distance: 3.0,
+                calm_distance: 8.0,
+            },
+            // ranging wide, shy of company
+            BehaviourKind::ReefShark => Traits {
+                roam: 16.0,
+                cruise_height: 1.0..5.0,
+                pace: 0.9,
+                wander_time: 10.0..25.0,
+                graze_chance: 0.0,
+                graze_time: 0.0..0.0,
+                rest_chance: 0.15,
+                rest_time: 20.0..60.0,
+                flee_distance: 2.5,
+                calm_distance: 6.0,
+            },
+        }
+    }
+}
+
+/// What an animal can find out about where it is.
+pub trait Surroundings {
+    /// Height of the seabed at `at`.
+    fn seabed(&self, at: Vec2) -> f32;
+    /// The nearest coral to `to`, if one is within `within`.
+    fn nearest_coral(&self, to: Vec3, within: f32) -> Option<Vec3>;
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Behaviour {
+    pub traits: Traits,
+    /// Where it wanders around.
+    pub home: Vec3,
+    pub state: BehaviourState,
+    /// Seconds before it decides what to do next; fleeing goes on for as
+    /// long as the player's close.
+    pub remaining: f32,
+    /// Where it's headed.
+    pub target: Vec3,
+}
+
+impl Behaviour {
+    /// Wandering, about to pick somewhere to go.
+    pub fn new(traits: Traits, home: Vec3) -> Self {
+        Behaviour {
+            traits,
+            home,
+            state: BehaviourState::Wander,
+            remaining: 0.0,
+            target: home,
+        }
+    }
+
+    /// Move on `dt` seconds for an animal at `position`, with the player
+    /// (if any) at `threat`, and say where to swim.
+    pub fn step(
+        &mut self,
+        dt: f32,
+        position: Vec3,
+        threat: Option<Vec3>,
+        surroundings: &impl Surroundings,
+        rng: &mut impl Rng,
+    ) -> SteeringTarget {
+        let threat_distance = threat.map(|threat| threat.distance(position));
+        if self.state == BehaviourState::Flee {
+            if threat_distance.is_none_or(|d| d >= self.traits.calm_distance) {
+                self.wander(surroundings, rng);
+            }
+        } else if threat_distance.is_some_and(|d| d < self.traits.flee_distance) {
+            self.state = BehaviourState::Flee;
+        } else {
+            self.remaining -= dt;
+            if self.remaining <= 0.0 {
+                self.decide(position, surroundings, rng);
+            }
+        }
+
+        if let (BehaviourState::Flee, Some(threat)) = (self.state, threat) {
+            let away = (position - threat).normalize_or(Vec3::X);
+            self.target = self.in_water(position + away * FLEE_REACH, surroundings);
+        }
+
+        let pace = match self.state {
+            BehaviourState::Wander => self.traits.pace,
+            BehaviourState::Graze | BehaviourState::Rest => self.traits.pace * 0.5,
+            BehaviourState::Flee => FLEE_PACE,
+        };
+        SteeringTarget {
+            point: Some(self.target),
+            pace,
+        }
+    }
+
+    /// What to do once the current state's run its course.
+    fn decide(&mut self, position: Vec3, surroundings: &impl Surroundings, rng: &mut impl Rng) {
+        if self.state != BehaviourState::Wander {
+            self.wander(surroundings, rng);
+            return;
+        }
+
+        let coral = (rng.random::<f32>() < self.traits.graze_chance)
+            .then(|| surroundings.nearest_coral(position, GRAZE_REACH))
+            .flatten();
+        if let Some(coral) = coral {
+            self.state = BehaviourState::Graze;
+            self.remaining = random_in(rng, &self.traits.graze_time);
+            self.target = coral + Vec3::Y * SETTLE_HEIGHT;
+        } else if rng.random::<f32>() < self.traits.rest_chance {
+            self.state = BehaviourState::Rest;
+            self.remaining = random_in(rng, &self.traits.rest_time);
+            let seabed = surroundings.seabed(position.xz());
+            self.target = position.with_y(seabed + SETTLE_HEIGHT);
+        } else {
+            self.wander(surroundings, rng);
+        }
+    }
+
+    /// Head off for a new spot around home.
+    fn wander(&mut self, surroundings: &impl Surroundings, rng: &mut impl Rng) {
+        self.state = BehaviourState::Wander;
+        self.remaining = random_in(rng, &self.traits.wander_time);
+        let spot = self.home.xz()
+            + Vec2::from_angle(rng.random_range(0.0..TAU))
+                * self.traits.roam
+                * rng.random::<f32>().sqrt();
+        let height = surroundings.seabed(spot) + random_in(rng, &self.traits.cruise_height);
+        self.target = self.in_water(spot.extend(height).xzy(), surroundings);
+    }
+
+    /// `point` moved up off the seabed or down under the surface as needed.
+    fn in_water(&self, point: Vec3, surroundings: &impl Surroundings) -> Vec3 {
+        let low = surroundings.seabed(point.xz()) + self.traits.cruise_height.start;
+        let high = WATER_SURFACE - SURFACE_CLEARANCE;
+        point.with_y(point.y.max(low).min(high))
+    }
+}
+
+/// A number in `range`, or its start when it's empty.
+fn random_in(rng: &mut impl Rng, range: &Range<f32>) -> f32 {
+    if range.is_empty() {
+        range.start
+    } else {
+        rng.random_range(range.clone())
+    }
+}
+
+struct World<'a, 'w, 's, 'd> {
+    region_sampler: &'a RegionSampler,
+    corals: &'a Query<'w, 's, &'d GlobalTransform, With<CoralHealth>>,
+}
+
+impl Surroundings for World<'_, '_, '_, '_> {
+    fn seabed(&self, at: Vec2) -> f32 {
+        self.region_sampler.sample_surface_height(at) as f32
+    }
+
+    fn nearest_coral(&self, to: Vec3, within: f32) -> Option<Vec3> {
+        self.corals
+            .iter()
+            .map(|coral| coral.translation())
+            .filter(|coral| coral.distance(to) <= within)
+            .min_by(|a, b| a.distance(to).total_cmp(&b.distance(to)))
+    }
+}
+
+fn behaviour_system(
+    time: Res<Time>,
+    mut rng: ResMut<BehaviourRng>,
+    region_sampler: Res<RegionSampler>,
+    player: Query<&GlobalTransform, With<FollowTarget>>,
+    corals: Query<&GlobalTransform, With<CoralHealth>>,
+    mut animals: Query<(&Transform, &mut Behaviour, &mut SteeringTarget)>,
+) {
+    let dt = time.delta_secs();
+    let threat = player.single().ok().map(|player_t| player_t.translation());
+    let world = World {
+        region_sampler: &region_sampler,
+        corals: &corals,
+    };
+
+    for (transform, mut behaviour, mut steering) in &mut animals {
+        *steering = behaviour.step(dt, transform.translation, threat, &world, &mut rng.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region_sampler::Region;
+    use karang_lestari::height_noise::HeightNoise;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+
+    /// Each update moves time on this much.
+    const TICK: Duration = Duration::from_millis(100);
+
+    /// A headless app with a flat seabed at 0 and the player at `player`.
+    fn test_app(player: Vec3) -> App {
+        let flat = Region::new("Flat".into(), 1, HeightNoise::Constant(0.0), vec![], vec![]);
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, BehaviourPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(TICK))
+            .insert_resource(RegionSampler::new(vec![flat], 64.0, 0.5, 8.0, 1))
+            .insert_resource(BehaviourRng::seeded(7));
+        app.world_mut()
+            .spawn((FollowTarget, GlobalTransform::from_translation(player)));
+        // the first update has no time passing
+        app.update();
+        app
+    }
+
+    /// An animal at `position` that stays put, so only its behaviour moves on.
+    fn spawn_animal(app: &mut App, behaviour: Behaviour, position: Vec3) -> Entity {
+        app.world_mut()
+            .spawn((
+                Transform::from_translation(position),
+                behaviour,
+                SteeringTarget::default(),
+            ))
+            .id()
+    }
+
+    fn behaviour(app: &App, animal: Entity) -> &Behaviour {
+        app.world().get::<Behaviour>(animal).unwrap()
+    }
+
+    fn move_player(app: &mut App, to: Vec3) {
+        let mut query = app
+            .world_mut()
+            .query_filtered::<&mut GlobalTransform, With<FollowTarget>>();
+        *query.single_mut(app.world_mut()).unwrap() = GlobalTransform::from_translation(to);
+    }
+
+    /// Settled into `state` with plenty of time left in it.
+    fn settled(state: BehaviourState, remaining: f32) -> Behaviour {
+        let mut behaviour = Behaviour::new(Traits::of(BehaviourKind::Turtle), Vec3::Y);
+        behaviour.state = state;
+        behaviour.remaining = remaining;
+        behaviour
+    }
+
+    #[test]
+    fn wander_turns_to_flee_inside_flee_distance() {
+        let traits = Traits::of(BehaviourKind::Turtle);
+        let mut app = test_app(Vec3::new(traits.flee_distance + 0.5, 1.0, 0.0));
+        let animal = spawn_animal(&mut app, settled(BehaviourState::Wander, 100.0), Vec3::Y);
+
+        app.update();
+        assert_eq!(behaviour(&app, animal).state, BehaviourState::Wander);
+
+        move_player(&mut app, Vec3::new(traits.flee_distance - 0.5, 1.0, 0.0));
+        app.update();
+        assert_eq!(behaviour(&app, animal).state, BehaviourState::Flee);
+        let steering = app.world().get::<SteeringTarget>(animal).unwrap();
+        assert!(steering.point.unwrap().x < 0.0, "flees away from the player");
+    }
+
+    #[test]
+    fn flee_turns_to_wander_only_past_calm_distance() {
+        let traits = Traits::of(BehaviourKind::Turtle);
+        let between = (traits.flee_distance + traits.calm_distance) * 0.5;
+        let mut app = test_app(Vec3::new(between, 1.0, 0.0));
+        let animal = spawn_animal(&mut app, settled(BehaviourState::Flee, 0.0), Vec3::Y);
+
+        app.update();
+        assert_eq!(behaviour(&app, animal).state, BehaviourState::Flee);
+
+        move_player(&mut app, Vec3::new(traits.calm_distance + 0.5, 1.0, 0.0));
+        app.update();
+        assert_eq!(behaviour(&app, animal).state, BehaviourState::Wander);
+    }
+
+    #[test]
+    fn grazes_only_with_coral_in_reach() {
+        let mut hungry = settled(BehaviourState::Wander, 0.0);
+        hungry.traits.graze_chance = 1.0;
+        hungry.traits.rest_chance = 0.0;
+
+        // too far off: wanders on instead
+        let mut app = test_app(Vec3::splat(100.0));
+        app.world_mut().spawn((
+            GlobalTransform::from_translation(Vec3::X * (GRAZE_REACH + 1.0)),
+            CoralHealth::new(1.0, 28.0),
+        ));
+        let animal = spawn_animal(&mut app, hungry.clone(), Vec3::Y);
+        app.update();
+        assert_eq!(behaviour(&app, animal).state, BehaviourState::Wander);
+
+        let mut app = test_app(Vec3::splat(100.0));
+        let coral = Vec3::X * (GRAZE_REACH - 1.0);
+        app.world_mut().spawn((
+            GlobalTransform::from_translation(coral),
+            CoralHealth::new(1.0, 28.0),
+        ));
+        let animal = spawn_animal(&mut app, hungry, Vec3::Y);
+        app.update();
+        let behaviour = behaviour(&app, animal);
+        assert_eq!(behaviour.state, BehaviourState::Graze);
+        assert_eq!(behaviour.target.xz(), coral.xz());
+    }
+
+    #[test]
+    fn rest_and_graze_turn_to_wander_when_time_runs_out() {
+        for state in [BehaviourState::Rest, BehaviourState::Graze] {
+            let mut app = test_app(Vec3::splat(100.0));
+            let lingering = spawn_animal(&mut app, settled(state, 100.0), Vec3::Y);
+            let finishing = spawn_animal(&mut app, settled(state, 0.05), Vec3::Y);
+
+            app.update();
+            assert_eq!(behaviour(&app, lingering).state, state);
+            assert_eq!(behaviour(&app, finishing).state, BehaviourState::Wander);
+            assert!(behaviour(&app, finishing).remaining > 0.0);
+        }
+    }
+}
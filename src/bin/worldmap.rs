@@ -0,0 +1,168 @@
+//! Renders a rectangle of the world to PNGs, without starting the game.
+//!
+//! cargo run --bin worldmap -- [--regions FILE] [--center X,Z] [--size W,H] [--scale UNITS] [--out DIR]
+//!
+//! Writes `heights.png` (16-bit greyscale, stretched to the min/max in view)
+//! and `regions.png` (each region's colour mixed by its blend weight, with
+//! Voronoi cell edges darkened).
+
+use glam::Vec2;
+use image::{ImageBuffer, Luma, Rgb};
+use karang_lestari::region_assets;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Distinct colours handed out to regions in file order.
+const PALETTE: [[f32; 3]; 8] = [
+    [0.93, 0.84, 0.58],
+    [0.40, 0.72, 0.45],
+    [0.90, 0.45, 0.55],
+    [0.25, 0.35, 0.75],
+    [0.95, 0.60, 0.25],
+    [0.55, 0.40, 0.80],
+    [0.35, 0.80, 0.85],
+    [0.60, 0.60, 0.60],
+];
+
+struct Options {
+    regions: PathBuf,
+    center: Vec2,
+    size: (u32, u32),
+    scale: f32,
+    out: PathBuf,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            regions: "assets/worlds/reef.regions.ron".into(),
+            center: Vec2::ZERO,
+            size: (512, 512),
+            scale: 0.25,
+            out: ".".into(),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("worldmap: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args(std::env::args().skip(1))?;
+
+    let bytes = std::fs::read(&options.regions)
+        .map_err(|e| format!("can't read {}: {e}", options.regions.display()))?;
+    let sampler = region_assets::parse_regions(&bytes)
+        .map_err(|e| format!("{}: {e}", options.regions.display()))?;
+
+    let (width, height) = options.size;
+    // world position of a pixel's centre, +Z pointing down the image
+    let origin = options.center - Vec2::new(width as f32, height as f32) * options.scale * 0.5;
+    let world = |x: u32, y: u32| origin + (Vec2::new(x as f32, y as f32) + 0.5) * options.scale;
+
+    let mut heights = Vec::with_capacity(width as usize * height as usize);
+    let mut regions = ImageBuffer::<Rgb<u8>, _>::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let p = world(x, y);
+            heights.push(sampler.sample_surface_height(p));
+
+            let (ids, weights) = sampler.sample_region(p);
+            let mut colour = [0.0; 3];
+            for (id, w) in ids.iter().zip(weights) {
+                for (c, p) in colour.iter_mut().zip(PALETTE[id % PALETTE.len()]) {
+                    *c += p * w;
+                }
+            }
+            // the two nearest sites are equally weighted along a cell edge
+            let edge = if weights[0] - weights[1] < 0.01 { 0.6 } else { 1.0 };
+            regions.put_pixel(x, y, Rgb(colour.map(|c| (c * edge * 255.0).round() as u8)));
+        }
+    }
+
+    let (min, max) = heights
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &h| (lo.min(h), hi.max(h)));
+    let range = (max - min).max(f64::EPSILON);
+    let heights = ImageBuffer::<Luma<u16>, _>::from_fn(width, height, |x, y| {
+        let h = heights[(y * width + x) as usize];
+        Luma([((h - min) / range * u16::MAX as f64).round() as u16])
+    });
+
+    std::fs::create_dir_all(&options.out)
+        .map_err(|e| format!("can't create {}: {e}", options.out.display()))?;
+    for (name, result) in [
+        ("heights.png", heights.save(options.out.join("heights.png"))),
+        ("regions.png", regions.save(options.out.join("regions.png"))),
+    ] {
+        result.map_err(|e| format!("can't write {name}: {e}"))?;
+    }
+
+    println!(
+        "{}x{} px at {} units/px, centred on ({}, {})",
+        width, height, options.scale, options.center.x, options.center.y
+    );
+    println!("heights: black = {min:.3}, white = {max:.3}");
+    for (id, region) in sampler.regions.iter().enumerate() {
+        let [r, g, b] = PALETTE[id % PALETTE.len()].map(|c| (c * 255.0).round() as u8);
+        println!("region {id}: #{r:02x}{g:02x}{b:02x} {}", region.name);
+    }
+    Ok(())
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    while let Some(flag) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+        match flag.as_str() {
+            "--regions" => options.regions = value()?.into(),
+            "--center" => {
+                let (x, z) = parse_pair::<f32>(&value()?)?;
+                if !x.is_finite() || !z.is_finite() {
+                    return Err("--center must be finite numbers".into());
+                }
+                options.center = Vec2::new(x, z);
+            }
+            "--size" => {
+                options.size = parse_pair(&value()?)?;
+                if options.size.0 == 0 || options.size.1 == 0 {
+                    return Err("--size must be at least one pixel each way".into());
+                }
+            }
+            "--scale" => {
+                options.scale = value()?
+                    .parse()
+                    .map_err(|_| "--scale wants a number".to_string())?;
+                // so NaN and infinity fail too
+                if !options.scale.is_finite() || options.scale <= 0.0 {
+                    return Err("--scale must be a finite number greater than zero".into());
+                }
+            }
+            "--out" => options.out = value()?.into(),
+            "-h" | "--help" => {
+                return Err(
+                    "usage: worldmap [--regions FILE] [--center X,Z] [--size W,H] [--scale UNITS] [--out DIR]"
+                        .into(),
+                );
+            }
+            other => return Err(format!("unknown argument {other}")),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_pair<T: std::str::FromStr>(text: &str) -> Result<(T, T), String> {
+    let bad = || format!("expected two comma-separated numbers, got \"{text}\"");
+    let (a, b) = text.split_once(',').ok_or_else(bad)?;
+    Ok((
+        a.trim().parse().map_err(|_| bad())?,
+        b.trim().parse().map_err(|_| bad())?,
+    ))
+}
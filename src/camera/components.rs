@@ -1,6 +1,3 @@
-use bevy::ecs::component::ComponentId;
-use crate::smooth_math::smooth_damp_vec2;
-use bevy::input::mouse::AccumulatedMouseMotion;
 use bevy::prelude::*;
 
 // Marker for your actual thing to follow
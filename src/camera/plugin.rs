@@ -1,9 +1,6 @@
 // src/camera/plugin.rs
 use bevy::prelude::*;
-use bevy::transform::systems::{propagate_parent_transforms, sync_simple_transforms};
-use crate::camera::components::{FollowTarget, SmoothOrbit};
 use crate::camera::systems::{spawn_camera_rig, smooth_orbit, smooth_follow};
-use crate::fishy::{fish_movement_system, FishMovement};
 
 /// A simple plugin that handles camera‐rig spawning and its follow/orbit logic.
 pub struct OrbitCameraPlugin;
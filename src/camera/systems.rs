@@ -1,9 +1,7 @@
 use crate::camera::components::*;
-use crate::smooth_math::{smooth_damp_angle, smooth_damp_vec2, smooth_damp_vec3};
-use bevy::ecs::component::ComponentId;
+use crate::smooth_math::{smooth_damp_angle, smooth_damp_vec3};
 use bevy::input::mouse::AccumulatedMouseMotion;
 use bevy::prelude::*;
-use crate::fishy::FishMovement;
 
 // Spawn function—call this after you’ve spawned your target entity
 pub fn spawn_camera_rig(mut commands: Commands, query_target: Query<Entity, With<FollowTarget>>) {
@@ -76,7 +74,7 @@ pub fn smooth_orbit(
     mouse_motion: Res<AccumulatedMouseMotion>,
     gamepads: Query<&Gamepad>,
     mut query: Query<(&mut Transform, &mut SmoothOrbit)>,
-    mut swimmer_query: Query<& Transform, (With<FishMovement>, Without<SmoothOrbit>)>,
+    swimmer_query: Query<& Transform, (With<FollowTarget>, Without<SmoothOrbit>)>,
 ) {
     let dt = time.delta_secs();
     let mouse_delta = mouse_motion.delta;
@@ -128,7 +126,7 @@ pub fn smooth_orbit(
         };
 
         // 4) damp from current → target
-        let (mut new_angle_x, new_vel_x) = smooth_damp_angle(
+        let (new_angle_x, new_vel_x) = smooth_damp_angle(
             orbit.angles.x,
             target_angles.x,
             orbit.velocity.x,
@@ -0,0 +1,87 @@
+use crate::chunked_env::{ChunkAssets, ChunkManager};
+use crate::floor_material::FloorMaterial;
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, LogDiagnosticsPlugin, RegisterDiagnostic,
+};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Chunk and asset counts as Bevy diagnostics. Over a long swim these
+/// should hover around a steady level; a count that keeps climbing is a
+/// leak.
+///
+/// Call `.add_plugins(ChunkDiagnosticsPlugin::default())` in your App.
+#[derive(Default)]
+pub struct ChunkDiagnosticsPlugin {
+    /// Also log the counts this often.
+    pub log_every: Option<Duration>,
+}
+
+impl ChunkDiagnosticsPlugin {
+    pub const LOADED_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("chunks/loaded");
+    pub const PENDING_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("chunks/pending");
+    pub const POOLED_CHUNKS: DiagnosticPath = DiagnosticPath::const_new("chunks/pooled");
+    pub const FLOOR_MATERIALS_CACHED: DiagnosticPath =
+        DiagnosticPath::const_new("chunks/floor_materials_cached");
+    pub const MESHES: DiagnosticPath = DiagnosticPath::const_new("assets/meshes");
+    pub const STANDARD_MATERIALS: DiagnosticPath =
+        DiagnosticPath::const_new("assets/standard_materials");
+    pub const FLOOR_MATERIALS: DiagnosticPath = DiagnosticPath::const_new("assets/floor_materials");
+    pub const IMAGES: DiagnosticPath = DiagnosticPath::const_new("assets/images");
+
+    pub const ALL: [DiagnosticPath; 8] = [
+        Self::LOADED_CHUNKS,
+        Self::PENDING_CHUNKS,
+        Self::POOLED_CHUNKS,
+        Self::FLOOR_MATERIALS_CACHED,
+        Self::MESHES,
+        Self::STANDARD_MATERIALS,
+        Self::FLOOR_MATERIALS,
+        Self::IMAGES,
+    ];
+}
+
+impl Plugin for ChunkDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        for path in Self::ALL {
+            // counts, not timings: no point averaging them
+            app.register_diagnostic(Diagnostic::new(path).with_max_history_length(1));
+        }
+        app.add_systems(Update, measure_chunk_diagnostics);
+
+        if let Some(wait_duration) = self.log_every {
+            app.add_plugins(LogDiagnosticsPlugin {
+                wait_duration,
+                filter: Some(Self::ALL.to_vec()),
+                ..default()
+            });
+        }
+    }
+}
+
+fn measure_chunk_diagnostics(
+    mut diagnostics: Diagnostics,
+    manager: Res<ChunkManager>,
+    chunk_assets: Res<ChunkAssets>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    floor_materials: Res<Assets<FloorMaterial>>,
+    images: Res<Assets<Image>>,
+) {
+    let counts = [
+        (ChunkDiagnosticsPlugin::LOADED_CHUNKS, manager.loaded_count()),
+        (ChunkDiagnosticsPlugin::PENDING_CHUNKS, manager.pending_count()),
+        (ChunkDiagnosticsPlugin::POOLED_CHUNKS, manager.pooled_count()),
+        (
+            ChunkDiagnosticsPlugin::FLOOR_MATERIALS_CACHED,
+            chunk_assets.floor_material_count(),
+        ),
+        (ChunkDiagnosticsPlugin::MESHES, meshes.len()),
+        (ChunkDiagnosticsPlugin::STANDARD_MATERIALS, materials.len()),
+        (ChunkDiagnosticsPlugin::FLOOR_MATERIALS, floor_materials.len()),
+        (ChunkDiagnosticsPlugin::IMAGES, images.len()),
+    ];
+    for (path, count) in counts {
+        diagnostics.add_measurement(&path, || count as f64);
+    }
+}
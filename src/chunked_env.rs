@@ -1,13 +1,21 @@
+use crate::coral_planting::PlantedCoral;
+use crate::density_mesh;
+use crate::floor_material::{self, FloorMaterial, FloorPalette};
 use crate::object_manager;
 use crate::object_manager::ObjectManager;
 use crate::region_sampler::RegionSampler;
+use crate::scatter;
+use crate::scatter::{CandidateCache, Placement};
+use crate::world_edits::{self, ChunkDiff, ChunkObject, ChunkObjectId, WorldEdits};
 use bevy::asset::RenderAssetUsages;
-use bevy::math::IVec2;
+use bevy::math::{Affine3A, IVec2};
 use bevy::prelude::*;
-use bevy::render::mesh::{Indices, PrimitiveTopology};
-use rand::Rng;
-use std::collections::{HashMap, HashSet};
-use std::f32::consts::PI;
+use bevy::render::mesh::{Indices, MeshAabb, PrimitiveTopology};
+use bevy::render::primitives::{Aabb, Frustum};
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::Arc;
 
 /// Call `.add_plugin(ChunkedEnvironmentPlugin::default())` in your App.
 pub struct ChunkedEnvironmentPlugin;
@@ -21,17 +29,55 @@ impl Default for ChunkedEnvironmentPlugin {
 impl Plugin for ChunkedEnvironmentPlugin {
     fn build(&self, app: &mut App) {
         app
-            // how many chunks along ±X and ±Z, and how large each one is
+            // how far out chunks load and unload, how large each one is,
+            // and how much detail they get with distance
             .insert_resource(ChunkSettings {
-                radius: 10,
+                radius: 24,
+                unload_radius: 27,
                 chunk_size: 2.0,
+                spawn_budget: 16,
+                build_budget: 32,
+                view_bias: 8.0,
+                pool_size: 256,
+                lod_levels: vec![
+                    LodLevel {
+                        within: 4,
+                        subdivisions: 10,
+                        details: true,
+                    },
+                    LodLevel {
+                        within: 10,
+                        subdivisions: 5,
+                        details: true,
+                    },
+                    LodLevel {
+                        within: 16,
+                        subdivisions: 2,
+                        details: false,
+                    },
+                    LodLevel {
+                        within: 24,
+                        subdivisions: 1,
+                        details: false,
+                    },
+                ],
             })
             // tracks loaded chunk entities
             .init_resource::<ChunkManager>()
+            .init_resource::<ChunkAssets>()
+            .add_event::<ChunkLoaded>()
+            .add_event::<ChunkUnloaded>()
             // runs every frame after camera has moved
             .add_systems(
                 Update,
-                chunk_manager_system.run_if(object_manager::asset_manager_ready),
+                chunk_manager_system
+                    .after(floor_material::load_floor_textures)
+                    .after(world_edits::check_world_fingerprint)
+                    .run_if(
+                        object_manager::asset_manager_ready
+                            .and(resource_exists::<RegionSampler>)
+                            .and(floor_material::floor_palette_ready),
+                    ),
             );
     }
 }
@@ -39,39 +85,315 @@ impl Plugin for ChunkedEnvironmentPlugin {
 /// Configure how many chunks to keep loaded, and their size
 #[derive(Resource)]
 pub struct ChunkSettings {
+    /// Chunks whose centre is within this many chunks of the camera's load in.
     pub radius: i32,
+    /// Loaded chunks stay until they're further than this, so swimming back
+    /// and forth over a boundary doesn't load and unload the same ring.
+    pub unload_radius: i32,
     pub chunk_size: f32,
+    /// Most finished chunks to spawn in a single frame.
+    pub spawn_budget: usize,
+    /// Most chunk builds running in the background at once. Free slots go
+    /// to the nearest chunks, and to ones in view before ones behind.
+    pub build_budget: usize,
+    /// Chunks outside the camera frustum queue as if they were this many
+    /// chunks further away.
+    pub view_bias: f32,
+    /// How many unloaded chunks to keep around (hidden) in case the camera
+    /// comes back; past that, the oldest are stripped and their entities
+    /// and meshes reused for new chunks.
+    pub pool_size: usize,
+    /// Nearest first. Chunks past the last level's `within` use the last level.
+    pub lod_levels: Vec<LodLevel>,
 }
 
-/// Keeps a map from chunk‐coords → spawned Entity
-#[derive(Resource)]
+/// How a chunk is built at a given distance from the camera.
+pub struct LodLevel {
+    /// Furthest chunk distance (in chunks, from the camera's chunk) this level covers.
+    pub within: i32,
+    /// Terrain grid resolution along each side of the chunk.
+    pub subdivisions: usize,
+    /// Whether the chunk gets its objects, or just the floor.
+    pub details: bool,
+}
+
+impl ChunkSettings {
+    /// Index into `lod_levels` for a chunk this many chunks from the camera.
+    pub fn lod_for(&self, distance: i32) -> usize {
+        self.lod_levels
+            .iter()
+            .position(|level| distance <= level.within)
+            .unwrap_or(self.lod_levels.len() - 1)
+    }
+
+    /// Whether a chunk `offset` chunks from the camera's is within `radius`.
+    fn within(offset: IVec2, radius: i32) -> bool {
+        offset.length_squared() <= radius * radius
+    }
+}
+
+/// A chunk has come into range and its entity is in the world (it may
+/// still be rebuilt at a different detail level later, without another
+/// event).
+#[derive(Event, Clone, Debug)]
+#[expect(dead_code, reason = "for plugins to hang things off chunks; the game only needs the coord so far")]
+pub struct ChunkLoaded {
+    pub coord: IVec2,
+    pub entity: Entity,
+    /// World-space bounds of the chunk's floor.
+    pub bounds: Aabb,
+}
+
+/// A chunk has gone out of range. Its entity may linger hidden for a while
+/// in case it's needed again, so don't rely on it being despawned.
+#[derive(Event, Clone, Debug)]
+pub struct ChunkUnloaded {
+    pub coord: IVec2,
+}
+
+/// Keeps a map from chunk‐coords → spawned chunk, plus the chunks
+/// still being generated in the background.
+#[derive(Resource, Default)]
 pub struct ChunkManager {
-    loaded: HashMap<IVec2, Entity>,
+    loaded: HashMap<IVec2, LoadedChunk>,
+    pending: HashMap<IVec2, PendingChunk>,
+    /// Unloaded but still intact (just hidden), oldest first.
+    parked: HashMap<IVec2, LoadedChunk>,
+    park_order: VecDeque<IVec2>,
+    /// Stripped chunks waiting to be reused by `spawn_chunk`.
+    spare: Vec<SpareChunk>,
+    context: Option<ChunkGenContext>,
 }
 
-impl Default for ChunkManager {
-    fn default() -> Self {
-        ChunkManager {
-            loaded: HashMap::new(),
+struct LoadedChunk {
+    entity: Entity,
+    floor: Entity,
+    /// The floor's mesh, overwritten in place when the chunk is rebuilt.
+    mesh: Handle<Mesh>,
+    bounds: Aabb,
+    /// Parent of the objects, if this chunk has them.
+    details: Option<Entity>,
+    lod: usize,
+}
+
+/// A chunk waiting for a build slot. Orders so a `BinaryHeap` pops the
+/// lowest `priority` first.
+struct ChunkRequest {
+    priority: f32,
+    coord: IVec2,
+    lod: usize,
+    with_details: bool,
+}
+
+impl PartialEq for ChunkRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ChunkRequest {}
+
+impl PartialOrd for ChunkRequest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ChunkRequest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .total_cmp(&self.priority)
+            .then_with(|| (other.coord.x, other.coord.y).cmp(&(self.coord.x, self.coord.y)))
+    }
+}
+
+/// A chunk entity and its floor with the details gone, ready to take on
+/// another coord.
+struct SpareChunk {
+    entity: Entity,
+    floor: Entity,
+    mesh: Handle<Mesh>,
+}
+
+struct PendingChunk {
+    lod: usize,
+    task: Task<ChunkBuild>,
+}
+
+impl ChunkManager {
+    /// The loaded chunk entity covering `world_pos`, if there is one.
+    #[expect(dead_code, reason = "for plugins to hang things off chunks")]
+    pub fn chunk_at(&self, world_pos: Vec3) -> Option<Entity> {
+        let chunk_size = self.context.as_ref()?.chunk_size;
+        let coord = (world_pos.xz() / chunk_size).floor().as_ivec2();
+        self.loaded.get(&coord).map(|chunk| chunk.entity)
+    }
+
+    /// Every chunk currently loaded, in no particular order.
+    pub fn loaded_coords(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.loaded.keys().copied()
+    }
+
+    pub fn loaded_count(&self) -> usize {
+        self.loaded.len()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Chunks kept hidden after unloading, plus stripped ones awaiting reuse.
+    pub fn pooled_count(&self) -> usize {
+        self.parked.len() + self.spare.len()
+    }
+
+    /// Hide an unloaded chunk, stripping the oldest parked one to a spare
+    /// (or despawning it, if there are spares enough) once over `pool_size`.
+    fn park(&mut self, commands: &mut Commands, coord: IVec2, chunk: LoadedChunk, pool_size: usize) {
+        commands.entity(chunk.entity).insert(Visibility::Hidden);
+        self.parked.insert(coord, chunk);
+        self.park_order.push_back(coord);
+
+        while self.park_order.len() > pool_size {
+            let oldest = self.park_order.pop_front().unwrap();
+            let chunk = self.parked.remove(&oldest).unwrap();
+            if self.spare.len() >= pool_size {
+                commands.entity(chunk.entity).despawn();
+                continue;
+            }
+            if let Some(details) = chunk.details {
+                commands.entity(details).despawn();
+            }
+            self.spare.push(SpareChunk {
+                entity: chunk.entity,
+                floor: chunk.floor,
+                mesh: chunk.mesh,
+            });
         }
     }
+
+    /// Bring a parked chunk back, if there is one for `coord`.
+    fn unpark(&mut self, commands: &mut Commands, coord: IVec2) -> Option<LoadedChunk> {
+        let chunk = self.parked.remove(&coord)?;
+        self.park_order.retain(|&c| c != coord);
+        commands.entity(chunk.entity).insert(Visibility::Inherited);
+        Some(chunk)
+    }
+}
+
+/// Materials every chunk shares, made once rather than per spawn so
+/// swimming around doesn't churn `Assets`. Rebuilt along with the
+/// chunks whenever the world definition changes.
+#[derive(Resource, Default)]
+pub struct ChunkAssets {
+    /// One per combination of floor weight slots seen so far.
+    floor_materials: HashMap<[usize; 4], Handle<FloorMaterial>>,
+}
+
+impl ChunkAssets {
+    pub fn floor_material_count(&self) -> usize {
+        self.floor_materials.len()
+    }
+
+    fn floor_material(
+        &mut self,
+        floor_materials: &mut Assets<FloorMaterial>,
+        floor_palette: &FloorPalette,
+        region_sampler: &RegionSampler,
+        slots: [usize; 4],
+    ) -> Handle<FloorMaterial> {
+        self.floor_materials
+            .entry(slots)
+            .or_insert_with(|| floor_materials.add(floor_palette.material(region_sampler, slots)))
+            .clone()
+    }
+}
+
+/// The world data chunk tasks read from, shared so each task is cheap to start.
+#[derive(Clone)]
+struct ChunkGenContext {
+    region_sampler: Arc<RegionSampler>,
+    object_manager: Arc<ObjectManager>,
+    chunk_size: f32,
+    /// Every subdivision count in use, so skirts can cover the worst mismatch.
+    lod_subdivisions: Arc<[usize]>,
+    /// Scatter candidates, shared between neighbouring chunks' builds.
+    candidates: Arc<CandidateCache>,
+}
+
+/// Everything about a chunk that can be worked out off the main thread.
+struct ChunkBuild {
+    coord: IVec2,
+    lod: usize,
+    mesh: Mesh,
+    /// World-space bounds of `mesh`.
+    bounds: Aabb,
+    /// Regions behind the mesh's four floor weights, see `splat_attributes`.
+    floor_slots: [usize; 4],
+    details: Option<ChunkDetails>,
+}
+
+struct ChunkDetails {
+    placements: Vec<Placement>,
 }
 
 /// Queries the camera each frame, figures out which chunk‐coords
-/// should be present, spawns new ones via `spawn_chunk`, and
-/// despawns the ones that fall out of range.
+/// should be present and at what detail, queues background builds for
+/// missing or out-of-date ones (reviving parked chunks where it can) and
+/// starts the most urgent within `build_budget`, applies finished builds
+/// (up to `spawn_budget` a frame, most urgent first) and parks the ones
+/// that fall out of range.
 fn chunk_manager_system(
     settings: Res<ChunkSettings>,
     mut manager: ResMut<ChunkManager>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    cam_tf: Query<&GlobalTransform, With<Camera3d>>,
-    coral_assets: Res<ObjectManager>,
-    asset_server: Res<AssetServer>,
+    mut floor_materials: ResMut<Assets<FloorMaterial>>,
+    mut chunk_assets: ResMut<ChunkAssets>,
+    floor_palette: Res<FloorPalette>,
+    camera: Query<(&GlobalTransform, &Frustum), With<Camera3d>>,
+    mut loaded_events: EventWriter<ChunkLoaded>,
+    mut unloaded_events: EventWriter<ChunkUnloaded>,
+    object_manager: Res<ObjectManager>,
     region_sampler: Res<RegionSampler>,
+    edits: Res<WorldEdits>,
 ) {
-    let cam_pos = cam_tf.single().unwrap().translation();
+    // refresh the snapshot tasks build from; if the world definition was
+    // reloaded, everything built from the old one has to go
+    if manager.context.is_none()
+        || region_sampler.is_changed()
+        || object_manager.is_changed()
+        || settings.is_changed()
+    {
+        if manager.context.is_some() {
+            let manager = &mut *manager;
+            for (coord, chunk) in manager.loaded.drain() {
+                commands.entity(chunk.entity).despawn();
+                unloaded_events.write(ChunkUnloaded { coord });
+            }
+            for (_, chunk) in manager.parked.drain() {
+                commands.entity(chunk.entity).despawn();
+            }
+            for spare in manager.spare.drain(..) {
+                commands.entity(spare.entity).despawn();
+            }
+            manager.park_order.clear();
+            manager.pending.clear();
+        }
+        manager.context = Some(ChunkGenContext {
+            region_sampler: Arc::new(region_sampler.clone()),
+            object_manager: Arc::new(object_manager.clone()),
+            chunk_size: settings.chunk_size,
+            lod_subdivisions: settings.lod_levels.iter().map(|l| l.subdivisions).collect(),
+            candidates: Arc::new(CandidateCache::new(SCATTER_CACHE_CHUNKS)),
+        });
+
+        *chunk_assets = ChunkAssets::default();
+    }
+
+    let (cam_tf, frustum) = camera.single().unwrap();
+    let cam_pos = cam_tf.translation();
     let cs = settings.chunk_size;
     // determine which chunk the camera is in
     let cam_chunk = IVec2::new(
@@ -79,273 +401,462 @@ fn chunk_manager_system(
         (cam_pos.z / cs).floor() as i32,
     );
 
-    // build the set of coords we *want*
-    let mut wanted = HashSet::new();
+    // build the set of coords we *want*, and the detail each should have
+    let mut wanted = HashMap::new();
     for dx in -settings.radius..=settings.radius {
         for dz in -settings.radius..=settings.radius {
-            wanted.insert(IVec2::new(cam_chunk.x + dx, cam_chunk.y + dz));
+            let offset = IVec2::new(dx, dz);
+            if !ChunkSettings::within(offset, settings.radius) {
+                continue;
+            }
+            let lod = settings.lod_for((offset.length_squared() as f32).sqrt().round() as i32);
+            wanted.insert(cam_chunk + offset, lod);
         }
     }
 
-    // spawn any missing chunks
-    for &coord in wanted.iter() {
-        if !manager.loaded.contains_key(&coord) {
-            let ent = spawn_chunk(
-                &mut commands,
-                &mut meshes,
-                &mut materials,
+    // a pending build for the wrong detail level is wasted work; dropping
+    // a task cancels it
+    manager
+        .pending
+        .retain(|coord, pending| wanted.get(coord) == Some(&pending.lod));
+
+    // how urgently a chunk is needed: distance from the camera in chunks,
+    // pushed back if it's out of view. The view test is against the whole
+    // water column the seabed could be in, so nothing has to be sampled here
+    // on the main thread
+    let half = cs * 0.5;
+    let column_half_height = (PRIORITY_COLUMN.end - PRIORITY_COLUMN.start) * 0.5;
+    let column_centre = PRIORITY_COLUMN.start + column_half_height;
+    let priority = |coord: IVec2| {
+        let centre = coord.as_vec2() * cs + Vec2::splat(half);
+        let column = Aabb {
+            center: Vec3A::new(centre.x, column_centre, centre.y),
+            half_extents: Vec3A::new(half, column_half_height, half),
+        };
+        let distance = (centre - cam_pos.xz()).length() / cs;
+        if frustum.intersects_obb(&column, &Affine3A::IDENTITY, false, true) {
+            distance
+        } else {
+            distance + settings.view_bias
+        }
+    };
+
+    // queue any missing or out-of-date chunks
+    let manager = &mut *manager;
+    let mut queue = BinaryHeap::new();
+    for (&coord, &lod) in wanted.iter() {
+        if manager.pending.contains_key(&coord) {
+            continue;
+        }
+        if !manager.loaded.contains_key(&coord)
+            && let Some(chunk) = manager.unpark(&mut commands, coord)
+        {
+            loaded_events.write(ChunkLoaded {
                 coord,
-                settings.chunk_size,
-                &coral_assets,
-                &asset_server,
-                &region_sampler,
-            );
-            manager.loaded.insert(coord, ent);
+                entity: chunk.entity,
+                bounds: chunk.bounds,
+            });
+            manager.loaded.insert(coord, chunk);
         }
+        let level = &settings.lod_levels[lod];
+        let with_details = match manager.loaded.get(&coord) {
+            None => level.details,
+            Some(chunk) if chunk.lod != lod => level.details && chunk.details.is_none(),
+            Some(_) => continue,
+        };
+        queue.push(ChunkRequest {
+            priority: priority(coord),
+            coord,
+            lod,
+            with_details,
+        });
     }
 
-    // despawn out-of-range chunks
-    manager.loaded.retain(|&coord, &mut ent| {
-        if wanted.contains(&coord) {
-            true
-        } else {
-            commands.entity(ent).despawn();
-            false
+    // start the most urgent, as far as the budget goes
+    let context = manager.context.clone().unwrap();
+    let task_pool = AsyncComputeTaskPool::get();
+    while manager.pending.len() < settings.build_budget
+        && let Some(request) = queue.pop()
+    {
+        let ChunkRequest {
+            coord,
+            lod,
+            with_details,
+            ..
+        } = request;
+        let context = context.clone();
+        let subdivisions = settings.lod_levels[lod].subdivisions;
+        let task = task_pool
+            .spawn(async move { build_chunk(&context, coord, lod, subdivisions, with_details) });
+        manager.pending.insert(coord, PendingChunk { lod, task });
+    }
+
+    // apply what's finished, most urgent first, within budget
+    let mut finished: Vec<(f32, IVec2)> = manager
+        .pending
+        .iter()
+        .filter(|(_, pending)| pending.task.is_finished())
+        .map(|(&coord, _)| (priority(coord), coord))
+        .collect();
+    finished.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (_, coord) in finished.into_iter().take(settings.spawn_budget) {
+        let pending = manager.pending.remove(&coord).unwrap();
+        let build = block_on(pending.task);
+        let keep_details = settings.lod_levels[build.lod].details;
+        let floor_material = chunk_assets.floor_material(
+            &mut floor_materials,
+            &floor_palette,
+            &region_sampler,
+            build.floor_slots,
+        );
+        match manager.loaded.get_mut(&coord) {
+            Some(chunk) => update_chunk(
+                &mut commands,
+                &mut meshes,
+                chunk,
+                build,
+                floor_material,
+                keep_details,
+                edits.chunk(coord),
+                &object_manager,
+            ),
+            None => {
+                let spare = manager.spare.pop();
+                let chunk = spawn_chunk(
+                    &mut commands,
+                    &mut meshes,
+                    spare,
+                    build,
+                    floor_material,
+                    settings.chunk_size,
+                    edits.chunk(coord),
+                    &object_manager,
+                );
+                loaded_events.write(ChunkLoaded {
+                    coord,
+                    entity: chunk.entity,
+                    bounds: chunk.bounds,
+                });
+                manager.loaded.insert(coord, chunk);
+            }
         }
+    }
+
+    // park chunks that have drifted past the unload radius
+    let unload_radius = settings.unload_radius.max(settings.radius);
+    let leaving: Vec<IVec2> = manager
+        .loaded
+        .keys()
+        .filter(|&&coord| !ChunkSettings::within(coord - cam_chunk, unload_radius))
+        .copied()
+        .collect();
+    for coord in leaving {
+        let chunk = manager.loaded.remove(&coord).unwrap();
+        manager.park(&mut commands, coord, chunk, settings.pool_size);
+        unloaded_events.write(ChunkUnloaded { coord });
+    }
+}
+
+/// Runs on the async compute pool: samples the terrain, builds the mesh and
+/// decides what goes where. Nothing here touches the ECS.
+fn build_chunk(
+    context: &ChunkGenContext,
+    coord: IVec2,
+    lod: usize,
+    subdivisions: usize,
+    with_details: bool,
+) -> ChunkBuild {
+    let chunk_size = context.chunk_size;
+    let region_sampler = &context.region_sampler;
+    let half = chunk_size * 0.5;
+    // center position of this chunk in world coords
+    let world_offset = coord.as_vec2() * chunk_size + Vec2::splat(half);
+
+    // density-field chunks can't hang skirts, so they always mesh at the
+    // finest level to keep their seams lined up
+    let mut mesh = if density_mesh::needs_density_mesh(region_sampler, chunk_size, world_offset) {
+        let finest = context.lod_subdivisions.iter().copied().max().unwrap_or(subdivisions);
+        density_mesh::generate_density_mesh(region_sampler, chunk_size, finest, world_offset)
+    } else {
+        let skirt = skirt_depth(
+            region_sampler,
+            chunk_size,
+            world_offset,
+            &context.lod_subdivisions,
+        );
+        generate_heightmap_mesh(region_sampler, chunk_size, subdivisions, world_offset, skirt)
+    };
+    let floor_slots = floor_material::splat_attributes(&mut mesh, region_sampler, world_offset);
+    let bounds = match mesh.compute_aabb() {
+        Some(local) => Aabb {
+            center: local.center + Vec3A::new(world_offset.x, 0.0, world_offset.y),
+            half_extents: local.half_extents,
+        },
+        None => Aabb::from_min_max(
+            Vec3::new(world_offset.x - half, 0.0, world_offset.y - half),
+            Vec3::new(world_offset.x + half, 0.0, world_offset.y + half),
+        ),
+    };
+
+    let details = with_details.then(|| ChunkDetails {
+        placements: scatter::scatter_chunk(
+            coord,
+            chunk_size,
+            region_sampler,
+            &context.object_manager,
+            &context.candidates,
+        ),
     });
+
+    ChunkBuild {
+        coord,
+        lod,
+        mesh,
+        bounds,
+        floor_slots,
+        details,
+    }
 }
 
-/// Example chunk‐factory: one plane + four little cubes.
-/// Edit this to plug in your own procedural geometry!
+/// Turns a finished `ChunkBuild` into entities: the floor and the
+/// scattered objects, all parented so the chunk despawns as one.
 fn spawn_chunk(
     commands: &mut Commands,
-    mut meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    coord: IVec2,
+    meshes: &mut Assets<Mesh>,
+    spare: Option<SpareChunk>,
+    build: ChunkBuild,
+    floor_material: Handle<FloorMaterial>,
     chunk_size: f32,
-    object_manager: &Res<ObjectManager>,
-    asset_server: &Res<AssetServer>,
-    region_sampler: &Res<RegionSampler>,
-) -> Entity {
-    let mut rng = rand::rng();
+    diff: Option<&ChunkDiff>,
+    object_manager: &ObjectManager,
+) -> LoadedChunk {
+    let coord = build.coord;
     let half = chunk_size * 0.5;
     // center position of this chunk in world coords
     let world_x = coord.x as f32 * chunk_size + half;
     let world_z = coord.y as f32 * chunk_size + half;
 
-    // prepare shared mesh/material handles
-    // let plane = meshes.add(Mesh::from(shape::Plane { size: chunk_size }));
-    // let plane_mat = materials.add(Color::from(Hsla { 0: Default::default() }).into());
-
-    // let plane = Mesh3d(
-    //     meshes.add(
-    //         Plane3d::default()
-    //             .mesh()
-    //             .size(chunk_size, chunk_size)
-    //             .subdivisions(10),
-    //     ),
-    // );
-    // let plane_mat = MeshMaterial3d(materials.add(Color::from(SILVER)));
-    let plane_mat = MeshMaterial3d(materials.add(StandardMaterial {
-        base_color_texture: Some(object_manager.floor_texture.clone()),
-        alpha_mode: AlphaMode::Opaque,
-        unlit: false,
-        perceptual_roughness: 0.95,
-        metallic: 0.6,
-        ..default()
-    }));
-    let plane = create_heightmap_mesh3d(
-        &mut meshes,
-        &region_sampler,
-        chunk_size,
-        10,
-        Vec2::new(world_x, world_z),
+    // a parent so we can despawn the whole chunk at once
+    let parent = (
+        Name::new(format!("Chunk({},{})", coord.x, coord.y)),
+        Transform::from_translation(Vec3::new(world_x, 0.0, world_z)),
+        Visibility::default(),
     );
+    let (entity, floor, mesh) = match spare {
+        Some(spare) => {
+            commands.entity(spare.entity).insert(parent);
+            // a new shape needs new bounds for culling
+            commands
+                .entity(spare.floor)
+                .insert(MeshMaterial3d(floor_material))
+                .remove::<Aabb>();
+            meshes.insert(&spare.mesh, build.mesh);
+            (spare.entity, spare.floor, spare.mesh)
+        }
+        None => {
+            let entity = commands.spawn(parent).id();
+            let mesh = meshes.add(build.mesh);
+            let floor = commands
+                .spawn((
+                    Mesh3d(mesh.clone()),
+                    MeshMaterial3d(floor_material),
+                    ChildOf(entity),
+                ))
+                .id();
+            (entity, floor, mesh)
+        }
+    };
+
+    let details = build
+        .details
+        .map(|details| spawn_details(commands, entity, coord, details, diff, object_manager));
+
+    LoadedChunk {
+        entity,
+        floor,
+        mesh,
+        bounds: build.bounds,
+        details,
+        lod: build.lod,
+    }
+}
+
+/// Swap a loaded chunk's floor for a build at a different detail level,
+/// adding or dropping its objects as that level asks.
+fn update_chunk(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    chunk: &mut LoadedChunk,
+    build: ChunkBuild,
+    floor_material: Handle<FloorMaterial>,
+    keep_details: bool,
+    diff: Option<&ChunkDiff>,
+    object_manager: &ObjectManager,
+) {
+    meshes.insert(&chunk.mesh, build.mesh);
+    chunk.bounds = build.bounds;
+    commands
+        .entity(chunk.floor)
+        .insert(MeshMaterial3d(floor_material))
+        .remove::<Aabb>();
+
+    if !keep_details
+        && let Some(details) = chunk.details.take()
+    {
+        commands.entity(details).despawn();
+    }
+    if let Some(details) = build.details
+        && chunk.details.is_none()
+    {
+        chunk.details = Some(spawn_details(
+            commands,
+            chunk.entity,
+            build.coord,
+            details,
+            diff,
+            object_manager,
+        ));
+    }
+    chunk.lod = build.lod;
+}
 
-    // spawn a parent so we can despawn the whole chunk at once
-    let parent = commands
+/// The objects (scattered, with any recorded edits applied),
+/// under one child so a chunk can lose them when it drops to a coarser level.
+fn spawn_details(
+    commands: &mut Commands,
+    chunk: Entity,
+    coord: IVec2,
+    details: ChunkDetails,
+    diff: Option<&ChunkDiff>,
+    object_manager: &ObjectManager,
+) -> Entity {
+    commands
         .spawn((
-            Name::new(format!("Chunk({},{})", coord.x, coord.y)),
-            Transform::from_translation(Vec3::new(world_x, 0.0, world_z)),
+            Name::new("Details"),
+            Transform::default(),
             Visibility::default(),
+            ChildOf(chunk),
         ))
-        .id();
-
-    // now attach the floor + some cubes
-    commands.entity(parent).with_children(|parent| {
-        // floor
-        parent.spawn((plane, plane_mat));
-        // four floating cubes at the corners
-        let cube_size = half * 0.01;
-        let cube_mesh = meshes.add(Mesh::from(Cuboid {
-            half_size: Vec3::new(cube_size, cube_size, cube_size) * 0.5,
-        }));
-        let cube_mat = materials.add(Color::srgb(0.8, 0.8, 0.9));
-        let r_y_amt = 1.0;
-        let r_xz_amt = chunk_size * 0.5;
-        let r_s_amt = 1.0;
-        let offsets = [
-            Vec3::new(
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                2.5 + rng.random_range(-r_y_amt..r_y_amt),
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                2.5 + rng.random_range(-r_y_amt..r_y_amt),
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                2.5 + rng.random_range(-r_y_amt..r_y_amt),
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                2.5 + rng.random_range(-r_y_amt..r_y_amt),
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                5.0 + rng.random_range(-r_y_amt..r_y_amt),
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                5.0 + rng.random_range(-r_y_amt..r_y_amt),
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                5.0 + rng.random_range(-r_y_amt..r_y_amt),
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-            Vec3::new(
-                -half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-                5.0 + rng.random_range(-r_y_amt..r_y_amt),
-                half * 0.5 + rng.random_range(-r_xz_amt..r_xz_amt),
-            ),
-        ];
-        for &off in &offsets {
-            parent.spawn((
-                Mesh3d(cube_mesh.clone()),
-                MeshMaterial3d(cube_mat.clone()),
-                Transform::from_translation(off),
-            ));
-        }
-
-        let (regions, _) = region_sampler.sample_region(Vec2::new(world_x, world_z));
-        let region = &region_sampler.regions[regions[0]];
-        // let set_ind = rng.random_range(0..region.objects.len());
-        // let obj_selection = region.objects[set_ind].clone();
-        // let scene = &object_manager.get(&*obj_selection.name).unwrap().model_handle;
-
-        let obj_name = region.pick_object();
-        let obj = object_manager.get(&*obj_name).unwrap();
-        let scene = &obj.model_handle;
-
-        let mut local_pos = Vec3::new(
-            rng.random_range(-r_xz_amt..r_s_amt),
-            0.0,
-            rng.random_range(-r_xz_amt..r_s_amt),
-        );
-        let world_pos_2d = Vec2::new(local_pos.x + world_x, local_pos.z + world_z);
-
-        // local_pos.y = region_sampler.sample_surface_height(world_pos_2d) as f32;
-
-        let size = obj.object_definition.size;
-        let yaw = rng.random_range(-PI..PI);
-        let yaw_matrix = Mat2::from_angle(yaw);
-
-        let height = region_sampler.sample_surface_height(world_pos_2d) as f32;
-
-        let sample = |offset: Vec2| region_sampler.sample_surface_height(world_pos_2d + offset) as f32;
-
-        let v_right_2d = yaw_matrix * (Vec2::X * size.x);
-        let v_up_2d = yaw_matrix * (Vec2::Y * size.y);
-
-        let hl = sample(-v_right_2d);
-        let hr = sample(v_right_2d);
-        let hd = sample(-v_up_2d);
-        let hu = sample(v_up_2d);
-
-        // approximate partial derivatives
-        let dh_dx = (hr - hl) / (2.0 * size.x);
-        let dh_dz = (hu - hd) / (2.0 * size.y);
-
-        // build the normal vector and normalize
-        let normal = Vec3::new(-dh_dx, 1.0, -dh_dz).normalize();
-
-        // rotate Y up → this normal
-        let rot = Quat::from_rotation_arc(Vec3::Y, normal)
-            * Quat::from_axis_angle(Vec3::Y, yaw);
-
+        .with_children(|parent| {
+            for placement in details.placements {
+                let transform = match diff {
+                    Some(diff) => match diff.scattered(&placement.key, placement.transform) {
+                        Some(transform) => transform,
+                        None => continue,
+                    },
+                    None => placement.transform,
+                };
+                let scene = &object_manager.get(&placement.name).unwrap().model_handle;
+                parent.spawn((
+                    Name::new(placement.name),
+                    SceneRoot(scene.clone()),
+                    transform,
+                    ChunkObject {
+                        coord,
+                        id: ChunkObjectId::Scattered(placement.key),
+                    },
+                ));
+            }
+
+            for (id, added) in diff.into_iter().flat_map(ChunkDiff::added) {
+                let Some(object) = object_manager.get(&added.name) else {
+                    warn!("Skipping added object {}, it's not in the catalogue", added.name);
+                    continue;
+                };
+                let mut entity = parent.spawn((
+                    Name::new(added.name.clone()),
+                    SceneRoot(object.model_handle.clone()),
+                    Transform::from(added.transform),
+                    ChunkObject {
+                        coord,
+                        id: ChunkObjectId::Added(id),
+                    },
+                ));
+                if let Some(planted_day) = added.planted_day {
+                    entity.insert(PlantedCoral {
+                        planted_day,
+                        scale: object.object_definition.scale.clone(),
+                    });
+                }
+            }
+        })
+        .id()
+}
 
-        // **** SIMPLE ORIENTATION ****
-        // let  ( rot_a, normal) = region_sampler.sample_surface_orientation(world_pos_2d, 0.1);
-        //
-        if normal.dot(Vec3::Y) < 0.88 {
-            // println!("Discard!");
-            return;
+/// How many chunks' scatter candidates to keep around: enough for the
+/// detailed chunks around the camera and the ring their borders reach into
+/// (a few hundred at the default settings), with room to spare for the
+/// ground most recently swum over.
+const SCATTER_CACHE_CHUNKS: usize = 4096;
+
+/// Heights a chunk's seabed could be anywhere between, generously, for
+/// deciding whether it's in view before it's been built.
+const PRIORITY_COLUMN: std::ops::Range<f32> = -40.0..30.0;
+
+/// Spacing of the height samples terrain normals are taken from.
+const NORMAL_EPS: f32 = 0.1;
+
+/// How far a chunk's skirts need to hang to hide the gap between its edges
+/// and a neighbour's at any detail level. Worked out from the edges alone,
+/// so both chunks along a seam agree on the worst case there. Each edge is
+/// sampled once at the finest level; coarser levels reuse those samples
+/// wherever their vertices land on them. Never less than one finest step,
+/// which covers a density-meshed neighbour: its edge vertices sit on the
+/// heightfield, but between the finest samples rather than on them.
+fn skirt_depth(
+    region_sampler: &RegionSampler,
+    chunk_size: f32,
+    world_offset: Vec2,
+    lod_subdivisions: &[usize],
+) -> f32 {
+    let finest = lod_subdivisions.iter().copied().max().unwrap_or(1).max(1);
+    let half = chunk_size * 0.5;
+    let corners = [
+        Vec2::new(-half, -half),
+        Vec2::new(half, -half),
+        Vec2::new(half, half),
+        Vec2::new(-half, half),
+    ];
+    let height = |local: Vec2| region_sampler.sample_surface_height(world_offset + local) as f32;
+
+    let mut worst = 0.0f32;
+    for side in 0..4 {
+        let (a, b) = (corners[side], corners[(side + 1) % 4]);
+        let edge: Vec<f32> = (0..=finest)
+            .map(|i| height(a.lerp(b, i as f32 / finest as f32)))
+            .collect();
+        // compare each level's straight segments against the finest samples
+        for &subdivisions in lod_subdivisions {
+            let subdivisions = subdivisions.max(1);
+            let vertices: Vec<f32> = if finest % subdivisions == 0 {
+                edge.iter().step_by(finest / subdivisions).copied().collect()
+            } else {
+                (0..=subdivisions)
+                    .map(|k| height(a.lerp(b, k as f32 / subdivisions as f32)))
+                    .collect()
+            };
+            for (i, &h) in edge.iter().enumerate() {
+                let t = i as f32 / finest as f32 * subdivisions as f32;
+                let seg = (t.floor() as usize).min(subdivisions - 1);
+                let (h0, h1) = (vertices[seg], vertices[seg + 1]);
+                worst = worst.max((h - (h0 + (h1 - h0) * (t - seg as f32))).abs());
+            }
         }
-        // let rot = rot_a * Quat::from_axis_angle(Vec3::Y, yaw);
-
-
-
-        // **** SINK PHASE ****
-
-        // Get the corners in 3D based on our actual orientation.
-        let v_right_3d = rot * (Vec3::X * size.x);
-        let v_up_3d = rot * (Vec3::Z * size.y);
-
-        // Convert back to 2D for sampling the EXACT height over these points.
-        // Optional: more accurate
-        // let v_right_2d = Vec2::new(v_right_3d.x, v_right_3d.z);
-        // let v_up_2d = Vec2::new(v_up_3d.x, v_up_3d.z);
-        // let hl = sample(-v_right_2d);
-        // let hr = sample(v_right_2d);
-        // let hd = sample(-v_up_2d);
-        // let hu = sample(v_up_2d);
-
-        // Find the distance offsets.
-        let d_l =  hl +v_right_3d.y;
-        let d_r =  hr - v_right_3d.y;
-        let d_d = hd + v_up_3d.y;
-        let d_u = hu-v_up_3d.y;
-
-        let y_pos_min = height.min(d_l).min(d_r).min(d_d).min(d_u);
-
-        // println!("height = {}, y_pos_min = {}", height, y_pos_min);
-        local_pos.y = y_pos_min;
-        // local_pos.y = height;
-
-        let scale = Vec3::ONE;// * rng.random_range(0.66..1.33);
-        parent.spawn((
-            SceneRoot(scene.clone()),
-            Transform {
-                translation: local_pos,
-                rotation: rot,
-                scale,
-                ..default()
-            },
-        ));
-    });
+    }
 
-    parent
+    // either side of a seam can be off by the worst case, plus a little slack
+    (worst * 2.0 + 0.05).max(chunk_size / finest as f32)
 }
 
-/// Compute a surface normal at x/z by sampling heights at ±eps,
-/// then return the quaternion that rotates Y-up onto that normal.
-
-/// Generates a heightmap Mesh from Perlin noise
+/// Generates a heightmap Mesh from Perlin noise, with a skirt hanging
+/// `skirt_depth` down from each edge to hide seams against other LODs.
 pub fn generate_heightmap_mesh(
-    region_sampler: &Res<RegionSampler>,
+    region_sampler: &RegionSampler,
     chunk_size: f32,
     subdivisions: usize,
     world_offset: Vec2,
+    skirt_depth: f32,
 ) -> Mesh {
-    // const HEIGHT_SCALE: f32 = 1.0;
-    // const SAMPLE_SCALE: f32 = 0.1;
     let verts_x = subdivisions + 1;
     let verts_z = subdivisions + 1;
 
@@ -360,17 +871,15 @@ pub fn generate_heightmap_mesh(
 
             let x_local = (u - 0.5) * chunk_size;
             let z_local = (v - 0.5) * chunk_size;
-            // let x_sample = (x_local + world_offset.x) * SAMPLE_SCALE;
-            // let z_sample = (z_local + world_offset.y) * SAMPLE_SCALE;
-            //
-            // let y = perlin.get([x_sample as f64, z_sample as f64]) as f32 * HEIGHT_SCALE;
-            let y = region_sampler.sample_surface_height(Vec2::new(
-                (x_local + world_offset.x),
-                (z_local + world_offset.y),
-            )) as f32;
+            let world = Vec2::new(x_local, z_local) + world_offset;
+            let y = region_sampler.sample_surface_height(world) as f32;
+            // from the height field itself at a fixed spacing, not the grid,
+            // so a seam vertex gets the same normal whatever either chunk's
+            // detail level
+            let normal = region_sampler.sample_surface_normal(world, NORMAL_EPS);
 
             positions.push([x_local, y, z_local]);
-            normals.push([0.0, 1.0, 0.0]);
+            normals.push(normal.to_array());
             uvs.push([u, v]);
         }
     }
@@ -386,6 +895,27 @@ pub fn generate_heightmap_mesh(
         }
     }
 
+    // walk the border so each skirt quad faces out of the chunk
+    let n = subdivisions;
+    let border: Vec<usize> = (0..n)
+        .chain((0..n).map(|iz| iz * verts_x + n))
+        .chain((0..n).map(|i| n * verts_x + n - i))
+        .chain((0..n).map(|i| (n - i) * verts_x))
+        .collect();
+    let skirt_start = positions.len();
+    for &i in &border {
+        let [x, y, z] = positions[i];
+        positions.push([x, y - skirt_depth, z]);
+        normals.push(normals[i]);
+        uvs.push(uvs[i]);
+    }
+    for k in 0..border.len() {
+        let next = (k + 1) % border.len();
+        let (a, b) = (border[k] as u32, border[next] as u32);
+        let (a_low, b_low) = ((skirt_start + k) as u32, (skirt_start + next) as u32);
+        indices.extend([a, b, a_low, b, b_low, a_low]);
+    }
+
     let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
@@ -393,20 +923,3 @@ pub fn generate_heightmap_mesh(
     mesh.insert_indices(Indices::U32(indices));
     mesh
 }
-
-/// Creates your Mesh3d handle for a terrain chunk
-/// Assumes you have a `Mesh3d` newtype around `Handle<Mesh>` in scope
-pub fn create_heightmap_mesh3d(
-    meshes: &mut Assets<Mesh>,
-    region_sampler: &Res<RegionSampler>,
-    chunk_size: f32,
-    subdivisions: usize,
-    world_offset: Vec2,
-) -> Mesh3d {
-    Mesh3d(meshes.add(generate_heightmap_mesh(
-        region_sampler,
-        chunk_size,
-        subdivisions,
-        world_offset,
-    )))
-}
@@ -0,0 +1,260 @@
+use crate::camera::components::FollowTarget;
+use crate::env_manager::EnvManager;
+use crate::object_manager::{ObjectManager, RestorationRole};
+use crate::world_edits::{self, AddedObject, ChunkObject, WorldEdits};
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use rand::Rng;
+use std::f32::consts::TAU;
+use std::ops::Range;
+
+/// In-game days a planted fragment takes to reach full size.
+const GROWTH_DAYS: f64 = 3.0;
+/// How close the turtle has to be to take or plant a fragment.
+const REACH: f32 = 1.0;
+/// Where a carried fragment sits, relative to the turtle, and how big it
+/// looks there compared to a freshly planted one.
+const CARRY_OFFSET: Vec3 = Vec3::new(0.0, -0.1, -0.35);
+const CARRY_SCALE: f32 = 0.4;
+
+/// The turtle takes fragments off corals and plants them on restoration
+/// structures, where they grow over the following in-game days. Planted
+/// fragments are kept in `WorldEdits`, along with the in-game clock they
+/// grow by.
+///
+/// Call `.add_plugins(CoralPlantingPlugin)` in your App, after
+/// `WorldEditsPlugin`.
+pub struct CoralPlantingPlugin;
+
+impl Plugin for CoralPlantingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Startup,
+            restore_world_clock.after(world_edits::load_world_edits),
+        )
+        .add_systems(
+            Update,
+            (coral_planting_system, grow_planted_corals, keep_world_clock),
+        );
+    }
+}
+
+/// On the turtle while it has a fragment to plant.
+#[derive(Component)]
+pub struct CarriedFragment {
+    pub name: String,
+    /// The fragment's model, riding along under the turtle.
+    model: Entity,
+}
+
+/// A fragment planted on a structure, growing through its object's scale
+/// range.
+#[derive(Component, Clone, Debug)]
+pub struct PlantedCoral {
+    /// In-game day it was planted.
+    pub planted_day: f64,
+    pub scale: Range<f32>,
+}
+
+impl PlantedCoral {
+    /// How big it is `days` into the world: the bottom of the range when
+    /// planted, easing out to the top after `GROWTH_DAYS`.
+    pub fn scale_at(&self, days: f64) -> f32 {
+        let t = ((days - self.planted_day) / GROWTH_DAYS).clamp(0.0, 1.0) as f32;
+        let t = 1.0 - (1.0 - t) * (1.0 - t);
+        self.scale.start.lerp(self.scale.end, t)
+    }
+}
+
+fn restore_world_clock(edits: Res<WorldEdits>, mut env_manager: ResMut<EnvManager>) {
+    if let Some(days) = edits.world_days() {
+        env_manager.set_days(days);
+    }
+}
+
+fn keep_world_clock(env_manager: Res<EnvManager>, mut edits: ResMut<WorldEdits>) {
+    edits.set_world_days(env_manager.days());
+}
+
+/// F (or the west face button) takes a fragment off a nearby coral, or,
+/// carrying one, plants it on the structure underneath.
+fn coral_planting_system(
+    mut commands: Commands,
+    kb: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    player: Query<(Entity, &GlobalTransform, Option<&CarriedFragment>), With<FollowTarget>>,
+    objects: Query<(Entity, &Name, &ChunkObject, &GlobalTransform, &ChildOf)>,
+    children: Query<&Children>,
+    mesh_bounds: Query<(&Aabb, &GlobalTransform)>,
+    transforms: Query<&GlobalTransform>,
+    object_manager: Res<ObjectManager>,
+    env_manager: Res<EnvManager>,
+    mut edits: ResMut<WorldEdits>,
+) {
+    let pressed = kb.just_pressed(KeyCode::KeyF)
+        || gamepads.iter().any(|gp| gp.just_pressed(GamepadButton::West));
+    if !pressed || !object_manager.catalogue_ready {
+        return;
+    }
+    let Ok((player, player_t, carried)) = player.single() else {
+        return;
+    };
+    let turtle = player_t.translation();
+
+    let with_role = |name: &Name, role| {
+        object_manager
+            .get(name.as_str())
+            .filter(|object| object.object_definition.restoration == Some(role))
+    };
+
+    match carried {
+        None => {
+            // the nearest coral in reach of its footprint
+            let nearest = objects
+                .iter()
+                .filter_map(|(_, name, _, object_t, _)| {
+                    let object = with_role(name, RestorationRole::Fragment)?;
+                    let (scale, _, position) = object_t.to_scale_rotation_translation();
+                    let footprint = object.object_definition.size.max_element() * scale.x;
+                    let distance = turtle.distance(position);
+                    (distance <= REACH + footprint).then_some((distance, object))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            let Some((_, object)) = nearest else {
+                return;
+            };
+
+            let def = &object.object_definition;
+            let model = commands
+                .spawn((
+                    Name::new("CarriedFragment"),
+                    SceneRoot(object.model_handle.clone()),
+                    Transform::from_translation(CARRY_OFFSET)
+                        .with_scale(Vec3::splat(def.scale.start * CARRY_SCALE)),
+                    ChildOf(player),
+                ))
+                .id();
+            commands.entity(player).insert(CarriedFragment {
+                name: def.name.clone(),
+                model,
+            });
+        }
+        Some(carried) => {
+            // the highest structure surface just below the turtle
+            let target = objects
+                .iter()
+                .filter(|(_, name, ..)| with_role(name, RestorationRole::Structure).is_some())
+                .filter_map(|(entity, _, chunk_object, _, child_of)| {
+                    let top = surface_below(entity, turtle, &children, &mesh_bounds)?;
+                    Some((top, chunk_object, child_of.parent()))
+                })
+                .max_by(|a, b| a.0.total_cmp(&b.0));
+            let Some((top, chunk_object, details)) = target else {
+                return;
+            };
+            let (Some(fragment), Ok(details_t)) =
+                (object_manager.get(&carried.name), transforms.get(details))
+            else {
+                return;
+            };
+
+            let planted = PlantedCoral {
+                planted_day: env_manager.days(),
+                scale: fragment.object_definition.scale.clone(),
+            };
+            let world = Vec3::new(turtle.x, top, turtle.z);
+            let transform = Transform {
+                translation: details_t.affine().inverse().transform_point3(world),
+                rotation: Quat::from_rotation_y(rand::rng().random_range(0.0..TAU)),
+                scale: Vec3::splat(planted.scale_at(planted.planted_day)),
+            };
+            let id = edits.add(
+                chunk_object.coord,
+                AddedObject {
+                    name: carried.name.clone(),
+                    transform: transform.into(),
+                    planted_day: Some(planted.planted_day),
+                },
+            );
+
+            commands.spawn((
+                Name::new(carried.name.clone()),
+                SceneRoot(fragment.model_handle.clone()),
+                transform,
+                id,
+                planted,
+                ChildOf(details),
+            ));
+            commands.entity(carried.model).despawn();
+            commands.entity(player).remove::<CarriedFragment>();
+        }
+    }
+}
+
+/// World height of the top of `structure`'s meshes under `point`, if
+/// `point` is over it and close enough to plant.
+fn surface_below(
+    structure: Entity,
+    point: Vec3,
+    children: &Query<&Children>,
+    mesh_bounds: &Query<(&Aabb, &GlobalTransform)>,
+) -> Option<f32> {
+    children
+        .iter_descendants(structure)
+        .filter_map(|entity| mesh_bounds.get(entity).ok())
+        .filter_map(|(aabb, transform)| {
+            let affine = transform.affine();
+            let center = affine.transform_point3a(aabb.center);
+            let m = affine.matrix3;
+            let half = m.x_axis.abs() * aabb.half_extents.x
+                + m.y_axis.abs() * aabb.half_extents.y
+                + m.z_axis.abs() * aabb.half_extents.z;
+            let (min, max) = (center - half, center + half);
+            let over = (min.x..=max.x).contains(&point.x) && (min.z..=max.z).contains(&point.z);
+            // a little leeway for the turtle's belly dipping into the top
+            let close = (-0.25..=REACH).contains(&(point.y - max.y));
+            (over && close).then_some(max.y)
+        })
+        .reduce(f32::max)
+}
+
+fn grow_planted_corals(
+    env_manager: Res<EnvManager>,
+    mut corals: Query<(&PlantedCoral, &mut Transform)>,
+) {
+    let days = env_manager.days();
+    for (coral, mut transform) in &mut corals {
+        let scale = Vec3::splat(coral.scale_at(days));
+        if transform.scale != scale {
+            transform.scale = scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn planted_coral_grows_through_its_scale_range() {
+        let coral = PlantedCoral {
+            planted_day: 10.0,
+            scale: 0.4..1.2,
+        };
+        assert_eq!(coral.scale_at(10.0), 0.4);
+        assert_eq!(coral.scale_at(3.0), 0.4);
+
+        let mut last = coral.scale_at(10.0);
+        for step in 1..=20 {
+            let scale = coral.scale_at(10.0 + GROWTH_DAYS * step as f64 / 20.0);
+            assert!(scale > last, "shrinks at step {step}");
+            last = scale;
+        }
+        // eases out: the first half of the growth is the bigger half
+        assert!(coral.scale_at(10.0 + GROWTH_DAYS * 0.5) > 0.8);
+
+        assert_eq!(coral.scale_at(10.0 + GROWTH_DAYS), 1.2);
+        assert_eq!(coral.scale_at(10.0 + GROWTH_DAYS * 3.0), 1.2);
+    }
+}
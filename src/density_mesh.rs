@@ -0,0 +1,264 @@
+use crate::region_sampler::{DensityColumn, RegionSampler};
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+/// Whether any of the chunk centred at `world_offset` reaches into a region
+/// with caves, and so needs `generate_density_mesh` instead of a heightmap.
+/// Tests the whole chunk, edges included (see
+/// `RegionSampler::caves_in_rect`), so a heightmap chunk never has caves
+/// anywhere in it.
+///
+/// Where a density chunk meets a heightmap one the shared edge is plain
+/// heightfield. The density side puts its edge vertices on the heightfield
+/// (see `generate_density_mesh`), but not at the same spots along the edge
+/// as the heightmap's, so the heightmap side's skirt covers the straight
+/// runs between them (see `skirt_depth`).
+pub fn needs_density_mesh(region_sampler: &RegionSampler, chunk_size: f32, world_offset: Vec2) -> bool {
+    let half = Vec2::splat(chunk_size * 0.5);
+    region_sampler.caves_in_rect(world_offset - half, world_offset + half)
+}
+
+/// Meshes the density field (see `RegionSampler::sample_density`) through a
+/// chunk with naive surface nets: one vertex per voxel the surface passes
+/// through, one quad per voxel edge it crosses.
+///
+/// Voxels sit on a world-aligned grid and each chunk only emits quads for
+/// the edges it owns (the half-open [0, subdivisions) range along x and z),
+/// so neighbouring chunks with the same `subdivisions` meet exactly. Vertices
+/// of the voxels on the chunk's edges are moved onto the edge, and onto the
+/// heightfield too wherever the edge has no caves.
+pub fn generate_density_mesh(
+    region_sampler: &RegionSampler,
+    chunk_size: f32,
+    subdivisions: usize,
+    world_offset: Vec2,
+) -> Mesh {
+    let n = subdivisions;
+    let step = chunk_size / n as f32;
+    let half = chunk_size * 0.5;
+
+    // grid points run from one step before the chunk to its far edge, so
+    // the voxels just outside the near edges exist to close the quads
+    let side = n + 2;
+    let local = |i: usize| (i as f32 - 1.0) * step - half;
+    let columns: Vec<DensityColumn> = (0..side * side)
+        .map(|c| {
+            let (ix, iz) = (c % side, c / side);
+            region_sampler.density_column(world_offset + Vec2::new(local(ix), local(iz)))
+        })
+        .collect();
+
+    // vertical extent: wherever rock and water can meet in any column
+    let (y_min, y_max) = columns.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), c| {
+        let reach = c.cave_reach();
+        (lo.min(c.height - reach), hi.max(c.height + reach))
+    });
+    let j0 = (y_min / step).floor() as i32 - 1;
+    let height = ((y_max / step).ceil() as i32 + 1 - j0 + 1) as usize;
+    let world_y = |j: usize| (j0 + j as i32) as f32 * step;
+
+    let point = |ix: usize, j: usize, iz: usize| (iz * height + j) * side + ix;
+    let mut density = vec![0.0f32; side * height * side];
+    for iz in 0..side {
+        for ix in 0..side {
+            let column = &columns[iz * side + ix];
+            for j in 0..height {
+                density[point(ix, j, iz)] = column.density(world_y(j));
+            }
+        }
+    }
+
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+
+    // one vertex per voxel with a sign change, at the mean of its crossings
+    let cells = side - 1;
+    let cell = |cx: usize, cy: usize, cz: usize| (cz * (height - 1) + cy) * cells + cx;
+    let mut cell_vertex = vec![u32::MAX; cells * (height - 1) * cells];
+    const CORNERS: [(usize, usize, usize); 8] = [
+        (0, 0, 0),
+        (1, 0, 0),
+        (0, 1, 0),
+        (1, 1, 0),
+        (0, 0, 1),
+        (1, 0, 1),
+        (0, 1, 1),
+        (1, 1, 1),
+    ];
+    const EDGES: [(usize, usize); 12] = [
+        (0, 1),
+        (2, 3),
+        (4, 5),
+        (6, 7),
+        (0, 2),
+        (1, 3),
+        (4, 6),
+        (5, 7),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+    ];
+    for cz in 0..cells {
+        for cy in 0..height - 1 {
+            for cx in 0..cells {
+                let d = CORNERS.map(|(dx, dy, dz)| density[point(cx + dx, cy + dy, cz + dz)]);
+                let solid = d.iter().filter(|&&v| v > 0.0).count();
+                if solid == 0 || solid == 8 {
+                    continue;
+                }
+
+                let mut sum = Vec3::ZERO;
+                let mut crossings = 0.0;
+                for (a, b) in EDGES {
+                    if (d[a] > 0.0) != (d[b] > 0.0) {
+                        let t = d[a] / (d[a] - d[b]);
+                        let corner = |c: usize| {
+                            let (dx, dy, dz) = CORNERS[c];
+                            Vec3::new(dx as f32, dy as f32, dz as f32)
+                        };
+                        sum += corner(a).lerp(corner(b), t);
+                        crossings += 1.0;
+                    }
+                }
+                let in_cell = sum / crossings;
+                let mut pos = Vec3::new(
+                    local(cx) + in_cell.x * step,
+                    world_y(cy) + in_cell.y * step,
+                    local(cz) + in_cell.z * step,
+                );
+
+                // voxels either side of the chunk's edges put their vertex on
+                // the edge itself, so the mesh stops exactly there; where the
+                // edge is plain heightfield it also takes the heightfield's
+                // height, to meet a heightmap neighbour. Both only depend on
+                // world position, so density neighbours snap the same way.
+                let snap = |c: usize, along: &mut f32| match c {
+                    0 => *along = -half,
+                    c if c == cells - 1 => *along = half,
+                    _ => {}
+                };
+                snap(cx, &mut pos.x);
+                snap(cz, &mut pos.z);
+                if pos.x.abs() == half || pos.z.abs() == half {
+                    let column = region_sampler.density_column(world_offset + Vec2::new(pos.x, pos.z));
+                    if column.cave_reach() <= 0.0 {
+                        pos.y = column.height;
+                    }
+                }
+
+                cell_vertex[cell(cx, cy, cz)] = positions.len() as u32;
+                positions.push(pos.to_array());
+                normals.push(
+                    region_sampler
+                        .sample_density_normal(pos + Vec3::new(world_offset.x, 0.0, world_offset.y), step * 0.5)
+                        .to_array(),
+                );
+                uvs.push([pos.x / chunk_size + 0.5, pos.z / chunk_size + 0.5]);
+            }
+        }
+    }
+
+    // one quad per owned grid edge with a sign change, wound so it faces
+    // out of the rock
+    let mut indices = Vec::new();
+    let mut quad = |corners: [(usize, usize, usize); 4], solid_first: bool| {
+        let v = corners.map(|(cx, cy, cz)| cell_vertex[cell(cx, cy, cz)]);
+        if v.contains(&u32::MAX) {
+            return;
+        }
+        if solid_first {
+            indices.extend([v[0], v[1], v[2], v[0], v[2], v[3]]);
+        } else {
+            indices.extend([v[0], v[2], v[1], v[0], v[3], v[2]]);
+        }
+    };
+    let owned = 1..n + 1;
+    for iz in owned.clone() {
+        for ix in owned.clone() {
+            for j in 0..height {
+                let d0 = density[point(ix, j, iz)];
+                let solid = d0 > 0.0;
+                // along x
+                if j > 0 && j < height - 1 && (density[point(ix + 1, j, iz)] > 0.0) != solid {
+                    quad(
+                        [
+                            (ix, j - 1, iz - 1),
+                            (ix, j, iz - 1),
+                            (ix, j, iz),
+                            (ix, j - 1, iz),
+                        ],
+                        solid,
+                    );
+                }
+                // along y
+                if j < height - 1 && (density[point(ix, j + 1, iz)] > 0.0) != solid {
+                    quad(
+                        [
+                            (ix - 1, j, iz - 1),
+                            (ix - 1, j, iz),
+                            (ix, j, iz),
+                            (ix, j, iz - 1),
+                        ],
+                        solid,
+                    );
+                }
+                // along z
+                if j > 0 && j < height - 1 && (density[point(ix, j, iz + 1)] > 0.0) != solid {
+                    quad(
+                        [
+                            (ix - 1, j - 1, iz),
+                            (ix, j - 1, iz),
+                            (ix, j, iz),
+                            (ix - 1, j, iz),
+                        ],
+                        solid,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::all());
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region_sampler::Region;
+    use karang_lestari::height_noise::HeightNoise;
+    use noise::Perlin;
+
+    #[test]
+    fn edge_vertices_sit_on_the_edge_and_the_heightfield() {
+        let hills = HeightNoise::Perlin {
+            perlin: Perlin::new(1),
+            scale: 0.3,
+            height: 2.0,
+        };
+        let region = Region::new("Hills".into(), 1, hills, vec![], vec![]);
+        let sampler = RegionSampler::new(vec![region], 64.0, 0.5, 8.0, 1);
+        let (chunk_size, offset) = (4.0, Vec2::new(6.0, -2.0));
+        let half = chunk_size * 0.5;
+
+        let mesh = generate_density_mesh(&sampler, chunk_size, 8, offset);
+        let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().as_float3().unwrap();
+        let mut on_edge = 0;
+        for &[x, y, z] in positions {
+            assert!(x.abs() <= half && z.abs() <= half, "({x}, {z}) is outside the chunk");
+            if x.abs() == half || z.abs() == half {
+                on_edge += 1;
+                let surface = sampler.sample_surface_height(offset + Vec2::new(x, z)) as f32;
+                assert!((y - surface).abs() < 1e-4, "edge vertex at {y}, heightfield at {surface}");
+            }
+        }
+        assert!(on_edge >= 4 * 8);
+    }
+}
@@ -1,8 +1,9 @@
+use crate::reef_health;
 use crate::region_sampler::{LightingSetup, RegionSampler};
 use bevy::app::{App, Plugin};
 use bevy::color::Srgba;
 use bevy::pbr::{DirectionalLight, DistanceFog};
-use bevy::prelude::{Camera3d, ClearColor, Component, GlobalTransform, Query, Res, ResMut, Resource, Time, Transform, Update, With, Without};
+use bevy::prelude::{resource_exists, Camera3d, IntoScheduleConfigs, ClearColor, Component, GlobalTransform, Query, Res, ResMut, Resource, Time, Transform, Update, With, Without};
 use glam::{FloatExt, Quat, Vec2, Vec3};
 use std::f32::consts::PI;
 
@@ -12,10 +13,14 @@ pub struct EnvManagerPlugin;
 impl Plugin for EnvManagerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(EnvManager {
+            day: 0,
             time_of_day: 0.65,
             time_of_day_speed: 0.003,
         })
-        .add_systems(Update, env_update_system);
+        .add_systems(
+            Update,
+            env_update_system.run_if(resource_exists::<RegionSampler>),
+        );
     }
 }
 
@@ -27,10 +32,45 @@ pub struct SecondaryLight {}
 
 #[derive(Resource)]
 pub struct EnvManager {
+    /// Whole in-game days gone by.
+    day: u32,
     time_of_day: f32,
     time_of_day_speed: f32,
 }
 
+impl EnvManager {
+    /// In-game days since the world began, including the part of today
+    /// that's gone.
+    pub fn days(&self) -> f64 {
+        self.day as f64 + self.time_of_day as f64
+    }
+
+    /// How far through today it is, 0..1: midnight at 0.25, noon at 0.75.
+    pub fn time_of_day(&self) -> f32 {
+        self.time_of_day
+    }
+
+    pub fn set_days(&mut self, days: f64) {
+        let days = days.max(0.0);
+        self.day = days as u32;
+        self.time_of_day = days.fract() as f32;
+    }
+
+    /// How much sunlight is getting into the water: 0 at night, 1 from
+    /// mid-morning to mid-afternoon.
+    pub fn daylight(&self) -> f32 {
+        // the main light turns about x with the time of day, pointing straight
+        // down at 0.75
+        let sun_height = -(self.time_of_day * PI * 2.0).sin();
+        ((sun_height + 0.1) / 0.6).clamp(0.0, 1.0)
+    }
+
+    /// The water temperature right now somewhere averaging `average` °C.
+    pub fn water_temperature(&self, average: f32) -> f32 {
+        reef_health::water_temperature(average, self.time_of_day)
+    }
+}
+
 pub fn env_update_system(
     time: Res<Time>,
     mut env_manager: ResMut<EnvManager>,
@@ -46,8 +86,11 @@ pub fn env_update_system(
     region_sampler: Res<RegionSampler>,
     mut clear_colour: ResMut<ClearColor>,
 ) {
-    env_manager.time_of_day =
-        (env_manager.time_of_day + time.delta_secs() * env_manager.time_of_day_speed) % 1.0;
+    let time_of_day = env_manager.time_of_day + time.delta_secs() * env_manager.time_of_day_speed;
+    if time_of_day >= 1.0 {
+        env_manager.day += 1;
+    }
+    env_manager.time_of_day = time_of_day % 1.0;
 
     let daytime = env_manager.time_of_day > 0.4 || env_manager.time_of_day_speed < 0.1;
 
@@ -68,7 +111,7 @@ pub fn env_update_system(
     let lighting_setup = tri_lerp_lighting([r1_lighting, r2_lighting, r3_lighting], [w1, w2, w3]);
 
     // primary
-    for (mut transform, mut directional, mut main_light) in query_main_light.iter_mut() {
+    for (mut transform, mut directional, _) in query_main_light.iter_mut() {
         transform.rotation = Quat::from_axis_angle(Vec3::X, env_manager.time_of_day * PI * 2.0);
 
         directional.shadows_enabled = daytime;
@@ -89,12 +132,8 @@ pub fn env_update_system(
     cam_fog.color = lighting_setup.fog_colour;
 }
 
-fn interpolate_lighting_setups(t: f32, lighting_setups: &Vec<LightingSetup>) -> LightingSetup {
-    let step_size = 1.0 / (lighting_setups.len() - 1) as f32;
-
-    let mut total = 0.0;
+fn interpolate_lighting_setups(t: f32, lighting_setups: &[LightingSetup]) -> LightingSetup {
     let mut start_ind = 0;
-    let mut remainder = 0.0;
     for i in 0..lighting_setups.len() - 1 {
         start_ind = i;
 
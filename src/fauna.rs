@@ -0,0 +1,436 @@
+use crate::behaviour::{Behaviour, Traits};
+use crate::camera::components::FollowTarget;
+use crate::chunked_env::{ChunkLoaded, ChunkManager, ChunkSettings, ChunkUnloaded};
+use crate::env_manager::EnvManager;
+use crate::fishy::{FishMovement, SteeringTarget, WATER_SURFACE};
+use crate::region_sampler::{FaunaSelection, RegionSampler, Species};
+use bevy::prelude::*;
+use rand::Rng;
+use std::ops::Range;
+
+/// Ambient animals: groups that flock together (separation, alignment,
+/// cohesion) around a home spot, keeping off the seabed and under the
+/// surface, or, for species with a `behaviour`, go about their own business
+/// around it (see `behaviour`). A population of them is kept up around the
+/// player, each group turning up in a chunk out of sight and going with it,
+/// or earlier when its time of day is over; which species turn up, where and
+/// when, is up to each region's `fauna` table.
+///
+/// Call `.add_plugins(FaunaPlugin::default())` in your App.
+pub struct FaunaPlugin {
+    /// Groups to keep about the player.
+    pub population: usize,
+    /// How far from the player new groups spawn: far enough to be lost in
+    /// the murk, near enough to be over loaded chunks.
+    pub spawn_distance: Range<f32>,
+}
+
+impl Default for FaunaPlugin {
+    fn default() -> Self {
+        FaunaPlugin {
+            population: 12,
+            spawn_distance: 16.0..40.0,
+        }
+    }
+}
+
+impl Plugin for FaunaPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FaunaSettings {
+            population: self.population,
+            spawn_distance: self.spawn_distance.clone(),
+        })
+        .add_systems(
+            Update,
+            (despawn_schools, spawn_schools, school_steering_system)
+                .chain()
+                .run_if(resource_exists::<RegionSampler>),
+        );
+    }
+}
+
+#[derive(Resource)]
+struct FaunaSettings {
+    population: usize,
+    spawn_distance: Range<f32>,
+}
+
+/// RNG stream for schools, see `RegionSampler::chunk_rng`.
+const SCHOOL_STREAM: u64 = 2;
+
+/// How far a school strays from home before it turns back.
+const HOME_RANGE: f32 = 6.0;
+/// Fish this many spacings apart count as neighbours for alignment and
+/// cohesion.
+const NEIGHBOUR_SPACINGS: f32 = 5.0;
+/// How far ahead, in seconds of swimming, a fish looks for the seabed.
+const LOOK_AHEAD: f32 = 1.5;
+/// Open water a fish keeps below and above it.
+const CLEARANCE: f32 = 0.5;
+/// How hard each urge pulls, in units/s² at full strength.
+const SEPARATION: f32 = 2.0;
+const ALIGNMENT: f32 = 1.0;
+const COHESION: f32 = 0.6;
+const HOMING: f32 = 1.0;
+const AVOIDANCE: f32 = 4.0;
+
+/// A group of animals, parent of its members: a school, or one on its own.
+/// Sits at the origin so the members' transforms are in world space.
+#[derive(Component)]
+pub struct School {
+    /// Chunk it turned up in, and goes with.
+    pub coord: IVec2,
+    /// The `fauna` entry it was picked from.
+    pub fauna: FaunaSelection,
+    /// Where it hangs around.
+    pub home: Vec3,
+}
+
+#[derive(Component)]
+pub struct SchoolFish {
+    pub velocity: Vec3,
+}
+
+/// Heights `fauna` swims at over seabed at `ground`, if its depth band
+/// reaches into the water there.
+fn swim_heights(fauna: &FaunaSelection, ground: f32) -> Option<Range<f32>> {
+    let low = (WATER_SURFACE - fauna.depth.end).max(ground + CLEARANCE * 2.0);
+    let high = (WATER_SURFACE - fauna.depth.start).min(WATER_SURFACE - CLEARANCE);
+    (low < high).then_some(low..high)
+}
+
+/// Top the population up by a group a frame. Groups turn up in a loaded
+/// chunk out of sight of the player, preferably one just loading in at the
+/// edge, and what turns up there is seeded from the chunk.
+fn spawn_schools(
+    mut commands: Commands,
+    mut loaded: EventReader<ChunkLoaded>,
+    settings: Res<FaunaSettings>,
+    chunk_settings: Res<ChunkSettings>,
+    schools: Query<&School>,
+    player: Query<&GlobalTransform, With<FollowTarget>>,
+    asset_server: Res<AssetServer>,
+    region_sampler: Res<RegionSampler>,
+    chunk_manager: Res<ChunkManager>,
+    env_manager: Res<EnvManager>,
+) {
+    let fresh: Vec<IVec2> = loaded.read().map(|event| event.coord).collect();
+    if schools.iter().count() >= settings.population {
+        return;
+    }
+    let Ok(player_t) = player.single() else {
+        return;
+    };
+
+    // one group to a chunk, and none in sight
+    let player = player_t.translation().xz();
+    let size = chunk_settings.chunk_size;
+    let centre = |coord: IVec2| (coord.as_vec2() + 0.5) * size;
+    let vacant = |coord: &IVec2| {
+        settings.spawn_distance.contains(&centre(*coord).distance(player))
+            && !schools.iter().any(|school| school.coord == *coord)
+    };
+    let mut candidates: Vec<IVec2> = fresh.into_iter().filter(vacant).collect();
+    if candidates.is_empty() {
+        candidates = chunk_manager.loaded_coords().filter(vacant).collect();
+    }
+    if candidates.is_empty() {
+        return;
+    }
+    // which chunk is down to chance; what turns up in it isn't
+    let coord = candidates[rand::rng().random_range(0..candidates.len())];
+
+    let mut rng = region_sampler.chunk_rng(coord, SCHOOL_STREAM);
+    let spot = coord.as_vec2() * size + Vec2::new(rng.random(), rng.random()) * size;
+    let region = &region_sampler.regions[region_sampler.pick_region(spot, rng.random())];
+    let ground = region_sampler.sample_surface_height(spot) as f32;
+    let time_of_day = env_manager.time_of_day();
+    let Some(fauna) = region.pick_fauna(&mut rng, |fauna| {
+        fauna.active_at(time_of_day) && swim_heights(fauna, ground).is_some()
+    }) else {
+        return;
+    };
+    let Some(heights) = swim_heights(fauna, ground) else {
+        return;
+    };
+
+    let species = &fauna.species;
+    let home = Vec3::new(spot.x, rng.random_range(heights), spot.y);
+    let model = asset_server.load(GltfAssetLabel::Scene(0).from_asset(species.model.clone()));
+
+    let count = rng.random_range(fauna.group_size.clone());
+    // start them in a loose ball, all heading roughly the same way
+    let radius = species.spacing * (count as f32).cbrt();
+    let heading = Vec3::new(rng.random_range(-1.0..1.0), 0.0, rng.random_range(-1.0..1.0))
+        .normalize_or(Vec3::X);
+    commands
+        .spawn((
+            Name::new(format!("School({})", species.name)),
+            School {
+                coord,
+                fauna: fauna.clone(),
+                home,
+            },
+            Transform::default(),
+            Visibility::default(),
+        ))
+        .with_children(|parent| {
+            for _ in 0..count {
+                let offset = Vec3::new(
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                ) * radius;
+                let velocity = heading * species.speed;
+                let transform = Transform::from_translation(home + offset).looking_to(velocity, Vec3::Y);
+                let turn = Quat::from_rotation_y(species.turn);
+                match species.behaviour {
+                    // its own animal: the model rides on a swimmer
+                    Some(kind) => {
+                        parent
+                            .spawn((
+                                Name::new(species.name.clone()),
+                                transform,
+                                Visibility::default(),
+                                swimmer(species),
+                                SteeringTarget::default(),
+                                Behaviour::new(Traits::of(kind), home),
+                            ))
+                            .with_child((
+                                SceneRoot(model.clone()),
+                                Transform::from_rotation(turn).with_scale(Vec3::splat(species.scale)),
+                            ));
+                    }
+                    None => {
+                        parent.spawn((
+                            SceneRoot(model.clone()),
+                            transform
+                                .with_rotation(transform.rotation * turn)
+                                .with_scale(Vec3::splat(species.scale)),
+                            SchoolFish { velocity },
+                        ));
+                    }
+                }
+            }
+        });
+}
+
+/// Movement for an animal of `species` going its own way, cruising at its
+/// speed.
+fn swimmer(species: &Species) -> FishMovement {
+    FishMovement {
+        acceleration: species.speed * 1.5,
+        deceleration: species.speed * 3.0,
+        lateral_deceleration: 6.0,
+        max_speed: species.speed,
+        sprint_multiplier: 2.0,
+        velocity: Vec3::ZERO,
+        target_direction: Vec3::ZERO,
+        current_go_force: 0.0,
+        rotation_smooth_time: 1.0,
+        go_scale_min: 0.25,
+        go_scale_max: 1.0,
+    }
+}
+
+/// Groups go with their chunk, or, out of the player's sight, when their
+/// time of day is over.
+fn despawn_schools(
+    mut commands: Commands,
+    mut unloaded: EventReader<ChunkUnloaded>,
+    settings: Res<FaunaSettings>,
+    schools: Query<(Entity, &School)>,
+    player: Query<&GlobalTransform, With<FollowTarget>>,
+    env_manager: Res<EnvManager>,
+) {
+    let gone: Vec<IVec2> = unloaded.read().map(|event| event.coord).collect();
+    let player = player.single().ok().map(|player_t| player_t.translation().xz());
+    let time_of_day = env_manager.time_of_day();
+
+    for (entity, school) in &schools {
+        let out_of_sight = player.is_none_or(|player| {
+            school.home.xz().distance(player) > settings.spawn_distance.start
+        });
+        let out_of_hours = !school.fauna.active_at(time_of_day) && out_of_sight;
+        if gone.contains(&school.coord) || out_of_hours {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn school_steering_system(
+    time: Res<Time>,
+    region_sampler: Res<RegionSampler>,
+    schools: Query<(&School, &Children)>,
+    mut fish: Query<(&mut Transform, &mut SchoolFish)>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {
+        return;
+    }
+
+    let mut members: Vec<(Vec3, Vec3)> = Vec::new();
+    for (school, children) in &schools {
+        members.clear();
+        members.extend(
+            children
+                .iter()
+                .filter_map(|child| fish.get(child).ok())
+                .map(|(t, f)| (t.translation, f.velocity)),
+        );
+
+        let species = &school.fauna.species;
+        let mut index = 0;
+        for child in children.iter() {
+            let Ok((mut transform, mut me)) = fish.get_mut(child) else {
+                continue;
+            };
+            let steer = flock(index, &members, species, school.home)
+                + avoid_terrain(transform.translation, me.velocity, &region_sampler);
+            index += 1;
+
+            // keep cruising: never stalling, never bolting
+            me.velocity = (me.velocity + steer * dt)
+                .clamp_length(species.speed * 0.5, species.speed * 1.5);
+
+            let moved = transform.translation + me.velocity * dt;
+            let moved = region_sampler.keep_clear(moved, CLEARANCE * 0.5);
+            transform.translation = moved.with_y(moved.y.min(WATER_SURFACE - CLEARANCE * 0.5));
+
+            let target = Transform::IDENTITY.looking_to(me.velocity, Vec3::Y).rotation
+                * Quat::from_rotation_y(species.turn);
+            transform.rotation = transform.rotation.slerp(target, 1.0 - (-dt * 4.0).exp());
+        }
+    }
+}
+
+/// The boids urges for member `index`: keep apart from close mates, match
+/// the neighbours' heading, drift to their middle, and head home when
+/// straying too far.
+fn flock(index: usize, members: &[(Vec3, Vec3)], species: &Species, home: Vec3) -> Vec3 {
+    let (position, velocity) = members[index];
+    let neighbour_range = species.spacing * NEIGHBOUR_SPACINGS;
+
+    let mut separation = Vec3::ZERO;
+    let mut heading = Vec3::ZERO;
+    let mut middle = Vec3::ZERO;
+    let mut neighbours = 0;
+    for (i, &(other, other_velocity)) in members.iter().enumerate() {
+        if i == index {
+            continue;
+        }
+        let away = position - other;
+        let distance = away.length();
+        if distance >= neighbour_range {
+            continue;
+        }
+        if distance < species.spacing {
+            // harder the closer they are
+            separation += away.normalize_or_zero() * (1.0 - distance / species.spacing);
+        }
+        heading += other_velocity;
+        middle += other;
+        neighbours += 1;
+    }
+
+    let mut steer = separation * SEPARATION;
+    if neighbours > 0 {
+        let n = neighbours as f32;
+        steer += (heading / n - velocity).clamp_length_max(species.speed) * ALIGNMENT;
+        steer += ((middle / n - position) / neighbour_range).clamp_length_max(1.0) * COHESION;
+    }
+
+    let from_home = position - home;
+    let stray = from_home.length() - HOME_RANGE;
+    if stray > 0.0 {
+        steer -= from_home.normalize_or_zero() * (stray / HOME_RANGE).min(1.0) * HOMING * species.speed;
+    }
+    steer
+}
+
+/// Pull up from seabed coming up ahead, and down from the surface.
+fn avoid_terrain(position: Vec3, velocity: Vec3, region_sampler: &RegionSampler) -> Vec3 {
+    let ahead = position + velocity * LOOK_AHEAD;
+    let ground = region_sampler.sample_surface_height(ahead.xz()) as f32;
+    let mut steer = Vec3::ZERO;
+
+    let above_ground = ahead.y - ground;
+    if above_ground < CLEARANCE * 2.0 {
+        steer.y += (1.0 - above_ground / (CLEARANCE * 2.0)).min(2.0) * AVOIDANCE;
+    }
+    let below_surface = WATER_SURFACE - ahead.y;
+    if below_surface < CLEARANCE * 2.0 {
+        steer.y -= (1.0 - below_surface / (CLEARANCE * 2.0)).min(2.0) * AVOIDANCE;
+    }
+    steer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn species() -> Species {
+        Species {
+            name: "Fish".into(),
+            model: "fish.glb".into(),
+            scale: 0.1,
+            speed: 1.0,
+            spacing: 0.5,
+            turn: 0.0,
+            behaviour: None,
+        }
+    }
+
+    fn fauna(depth: Range<f32>) -> FaunaSelection {
+        FaunaSelection {
+            species: species(),
+            weight: 1,
+            depth,
+            active: 0.0..1.0,
+            group_size: 1..=1,
+        }
+    }
+
+    #[test]
+    fn swim_heights_keep_to_the_depth_band() {
+        let heights = swim_heights(&fauna(2.0..4.0), WATER_SURFACE - 10.0).unwrap();
+        assert_eq!(heights, WATER_SURFACE - 4.0..WATER_SURFACE - 2.0);
+        // the seabed cuts into the band
+        let heights = swim_heights(&fauna(2.0..4.0), WATER_SURFACE - 3.5).unwrap();
+        assert_eq!(heights.start, WATER_SURFACE - 3.5 + CLEARANCE * 2.0);
+    }
+
+    #[test]
+    fn swim_heights_none_when_the_band_is_under_the_seabed() {
+        assert_eq!(swim_heights(&fauna(5.0..8.0), WATER_SURFACE - 3.0), None);
+        assert_eq!(swim_heights(&fauna(0.0..1.0), WATER_SURFACE + 1.0), None);
+    }
+
+    #[test]
+    fn separation_pushes_close_mates_apart() {
+        let species = species();
+        let heading = Vec3::X;
+        let members = [(Vec3::ZERO, heading), (Vec3::new(0.0, 0.0, 0.2), heading)];
+        for index in 0..2 {
+            let away = members[index].0 - members[1 - index].0;
+            let steer = flock(index, &members, &species, Vec3::ZERO);
+            assert!(steer.dot(away) > 0.0, "{index} steers {steer}");
+        }
+
+        // beyond `spacing` they draw together instead
+        let members = [(Vec3::ZERO, heading), (Vec3::new(0.0, 0.0, 1.0), heading)];
+        assert!(flock(0, &members, &species, Vec3::ZERO).z > 0.0);
+    }
+
+    #[test]
+    fn homing_pulls_back_past_home_range() {
+        let species = species();
+        let home = Vec3::new(3.0, -2.0, 1.0);
+        let near = [(home + Vec3::X * HOME_RANGE * 0.9, Vec3::ZERO)];
+        assert_eq!(flock(0, &near, &species, home), Vec3::ZERO);
+
+        let far = [(home + Vec3::X * HOME_RANGE * 1.5, Vec3::ZERO)];
+        let steer = flock(0, &far, &species, home);
+        assert!(steer.x < 0.0 && steer.y == 0.0 && steer.z == 0.0, "steers {steer}");
+    }
+}
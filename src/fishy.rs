@@ -24,7 +24,21 @@ pub struct FishMovement {
     pub go_scale_max: f32,
 }
 
+/// Swims a `FishMovement` somewhere other than where the keyboard says:
+/// toward `point`, at `pace` times its top speed (above 1 is sprinting, up
+/// to its sprint multiplier). With no point it slows to a stop.
+#[derive(Component, Clone, Debug, Default)]
+pub struct SteeringTarget {
+    pub point: Option<Vec3>,
+    pub pace: f32,
+}
+
 const STICK_DEAD_ZONE: f32 = 0.15;
+/// A steered swimmer this close to its point counts as there.
+const ARRIVE_DISTANCE: f32 = 0.2;
+
+/// Height of the sea surface; nothing swims above it.
+pub const WATER_SURFACE: f32 = 12.0;
 
 fn apply_radial_deadzone(raw: Vec2, dead_zone: f32) -> Vec2 {
     let mag = raw.length();
@@ -42,7 +56,7 @@ pub fn fish_movement_system(
     time: Res<Time>,
     kb: Res<ButtonInput<KeyCode>>,
     gamepads: Query<&Gamepad>,
-    mut query: Query<(&mut Transform, &mut FishMovement)>,
+    mut query: Query<(&mut Transform, &mut FishMovement), Without<SteeringTarget>>,
     // get camera’s global transform to derive forward/pan axes:
     cam_tf: Query<&GlobalTransform, With<Camera3d>>,
     region_sampler: Res<RegionSampler>,
@@ -140,95 +154,139 @@ pub fn fish_movement_system(
             1.0
         };
 
-        // 5) Smoothly rotate toward the *target* direction
-        if desired_vel.length_squared() > 1e-6 {
-            // a) compute target direction (unit)
-            fish_movement.target_direction = desired_vel.normalize();
+        swim(&mut tx, &mut fish_movement, desired_vel, speed, dt, &region_sampler);
+    }
+}
+
+/// Swim toward a `SteeringTarget`'s point with the same physics the keyboard
+/// drives.
+pub fn steered_movement_system(
+    time: Res<Time>,
+    mut query: Query<(&mut Transform, &mut FishMovement, &SteeringTarget)>,
+    region_sampler: Res<RegionSampler>,
+) {
+    let dt = time.delta_secs();
+    for (mut tx, mut fish_movement, steering) in &mut query {
+        let pace = steering.pace.clamp(0.0, fish_movement.sprint_multiplier);
+        let speed = fish_movement.max_speed * pace;
+        let to_point = steering
+            .point
+            .map_or(Vec3::ZERO, |point| point - tx.translation);
+        let distance = to_point.length();
+
+        // let go in time to coast to a stop on the point, rather than
+        // overshooting and circling it
+        let braking = fish_movement.velocity.length_squared() / (2.0 * fish_movement.deceleration);
+        let desired_vel = if distance > braking.max(ARRIVE_DISTANCE) {
+            to_point / distance * speed
         } else {
-            fish_movement.target_direction = tx.forward().into();
-        }
+            Vec3::ZERO
+        };
+        fish_movement.current_go_force = if desired_vel.length_squared() > 0.01 {
+            pace
+        } else {
+            0.0
+        };
 
-        // b) build a temp Transform so we can call look_at()
-        let mut tmp = Transform::default();
-        tmp.look_at(fish_movement.target_direction, Vec3::Y);
-        let target_rot = tmp.rotation;
+        swim(&mut tx, &mut fish_movement, desired_vel, speed, dt, &region_sampler);
+    }
+}
 
-        // c) compute an exponential smoothing factor in [0,1]
-        //    such that small delta_secs = slow start,
-        //    larger dt gives faster catchup,
-        //    and smooth_time is the 63%-to-target time constant.
-        let t = 1.0 - (-dt / fish_movement.rotation_smooth_time).exp();
+/// Turn toward `desired_vel` and accelerate along the way we face, bleeding
+/// off sideways drift; coast to a stop when there's nowhere to go.
+fn swim(
+    tx: &mut Transform,
+    fish_movement: &mut FishMovement,
+    desired_vel: Vec3,
+    speed: f32,
+    dt: f32,
+    region_sampler: &RegionSampler,
+) {
+    // 5) Smoothly rotate toward the *target* direction
+    if desired_vel.length_squared() > 1e-6 {
+        // a) compute target direction (unit)
+        fish_movement.target_direction = desired_vel.normalize();
+    } else {
+        fish_movement.target_direction = tx.forward().into();
+    }
 
-        // d) slerp current→target by that factor
-        tx.rotation = tx.rotation.slerp(target_rot, t);
+    // b) build a temp Transform so we can call look_at()
+    let mut tmp = Transform::default();
+    tmp.look_at(fish_movement.target_direction, Vec3::Y);
+    let target_rot = tmp.rotation;
 
-        let go_dot = fish_movement.target_direction.dot(tx.forward().into());
+    // c) compute an exponential smoothing factor in [0,1]
+    //    such that small delta_secs = slow start,
+    //    larger dt gives faster catchup,
+    //    and smooth_time is the 63%-to-target time constant.
+    let t = 1.0 - (-dt / fish_movement.rotation_smooth_time).exp();
 
-        let go_0_1 = go_dot * 0.5 + 0.5;
+    // d) slerp current→target by that factor
+    tx.rotation = tx.rotation.slerp(target_rot, t);
 
-        let go_scale = bevy::prelude::FloatExt::lerp(fish_movement.go_scale_min, 1.0, go_0_1);
+    let go_dot = fish_movement.target_direction.dot(tx.forward().into());
 
-        // // 3) Accelerate / decelerate
-        // // let diff = desired_vel - fish_movement.velocity;
-        // // if no input, use deceleration; otherwise acceleration
-        // let accel_rate = if desired_vel.length_squared() < 0.001 {
-        //     fish_movement.deceleration
-        // } else {
-        //     fish_movement.acceleration * go_scale
-        // };
-        // // limit how much we can change velocity this frame
+    let go_0_1 = go_dot * 0.5 + 0.5;
 
-        // let delta_v = tx.forward().clamp_length_max(accel_rate * dt);
-        // fish_movement.velocity += delta_v;
+    let go_scale = bevy::prelude::FloatExt::lerp(fish_movement.go_scale_min, fish_movement.go_scale_max, go_0_1);
 
-        if desired_vel.length_squared() > 0.01 {
-            let accel_rate = fish_movement.acceleration * go_scale;
-            let delta_v = tx.forward().clamp_length_max(accel_rate * dt);
-            fish_movement.velocity += delta_v;
+    // // 3) Accelerate / decelerate
+    // // let diff = desired_vel - fish_movement.velocity;
+    // // if no input, use deceleration; otherwise acceleration
+    // let accel_rate = if desired_vel.length_squared() < 0.001 {
+    //     fish_movement.deceleration
+    // } else {
+    //     fish_movement.acceleration * go_scale
+    // };
+    // // limit how much we can change velocity this frame
 
-            // hack
-            // fish_movement.velocity = tx.forward() * speed * go_scale
-        } else {
-            let diff = desired_vel - fish_movement.velocity;
-            let delta_v = diff.clamp_length_max(fish_movement.deceleration * dt);
-            fish_movement.velocity += delta_v;
+    // let delta_v = tx.forward().clamp_length_max(accel_rate * dt);
+    // fish_movement.velocity += delta_v;
 
-            // hack
-            // fish_movement.velocity = Vec3::ZERO;
-        }
+    if desired_vel.length_squared() > 0.01 {
+        let accel_rate = fish_movement.acceleration * go_scale;
+        let delta_v = tx.forward().clamp_length_max(accel_rate * dt);
+        fish_movement.velocity += delta_v;
 
-        // dampen lateral
-        if fish_movement.velocity.length_squared() > 0.01 {
-            let fwd_vel = fish_movement.velocity.project_onto(tx.forward().into());
+        // hack
+        // fish_movement.velocity = tx.forward() * speed * go_scale
+    } else {
+        let diff = desired_vel - fish_movement.velocity;
+        let delta_v = diff.clamp_length_max(fish_movement.deceleration * dt);
+        fish_movement.velocity += delta_v;
 
-            // neg of all lateral movement
-            let diff = fwd_vel - fish_movement.velocity;
-            let delta_v = diff.clamp_length_max(fish_movement.lateral_deceleration * dt);
-            fish_movement.velocity += delta_v;
-        }
+        // hack
+        // fish_movement.velocity = Vec3::ZERO;
+    }
 
-        // clamp it
-        fish_movement.velocity = fish_movement
-            .velocity
-            .clamp_length_max(speed * fish_movement.sprint_multiplier);
-
-        // 4) Apply motion
-        tx.translation += fish_movement.velocity * dt;
-
-        // info!(
-        //         "dt = {:.4}, fish_movement.velocity = {:.4}, fish_movement.velocity.length = {:.4}",
-        //         dt,
-        //         fish_movement.velocity,
-        //         fish_movement.velocity.length(),
-        //     );
-
-        let pos_2d = Vec2::new(tx.translation.x, tx.translation.z);
-        let height = region_sampler.sample_surface_height(pos_2d) as f32;
-        if tx.translation.y < height + 0.3 {
-            tx.translation.y = height + 0.3;
-        }
-        if tx.translation.y > 12.0 {
-            tx.translation.y = 12.0;
-        }
+    // dampen lateral
+    if fish_movement.velocity.length_squared() > 0.01 {
+        let fwd_vel = fish_movement.velocity.project_onto(tx.forward().into());
+
+        // neg of all lateral movement
+        let diff = fwd_vel - fish_movement.velocity;
+        let delta_v = diff.clamp_length_max(fish_movement.lateral_deceleration * dt);
+        fish_movement.velocity += delta_v;
+    }
+
+    // clamp it
+    fish_movement.velocity = fish_movement
+        .velocity
+        .clamp_length_max(speed * fish_movement.sprint_multiplier);
+
+    // 4) Apply motion
+    tx.translation += fish_movement.velocity * dt;
+
+    // info!(
+    //         "dt = {:.4}, fish_movement.velocity = {:.4}, fish_movement.velocity.length = {:.4}",
+    //         dt,
+    //         fish_movement.velocity,
+    //         fish_movement.velocity.length(),
+    //     );
+
+    // stay off the seabed (and out of cave walls)
+    tx.translation = region_sampler.keep_clear(tx.translation, 0.3);
+    if tx.translation.y > WATER_SURFACE {
+        tx.translation.y = WATER_SURFACE;
     }
 }
@@ -0,0 +1,278 @@
+use crate::region_sampler::RegionSampler;
+use crate::shader_uniforms::FloorSplatUniform;
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::render::render_resource::{
+    AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat, TextureViewDescriptor,
+    TextureViewDimension,
+};
+use std::collections::HashMap;
+
+/// The seabed material: a `StandardMaterial` whose base colour is painted
+/// from each region's floor set, blended by the weights the chunk mesh carries.
+pub type FloorMaterial = ExtendedMaterial<StandardMaterial, FloorSplat>;
+
+/// Call `.add_plugins(FloorMaterialPlugin)` in your App.
+pub struct FloorMaterialPlugin;
+
+impl Plugin for FloorMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<FloorMaterial>::default())
+            .init_resource::<FloorPalette>()
+            .add_systems(
+                Update,
+                (load_floor_textures, build_floor_array)
+                    .chain()
+                    .run_if(resource_exists::<RegionSampler>),
+            );
+    }
+}
+
+/// Slope (1 − normal.y) where the steep layer starts to show, and where it
+/// has fully taken over.
+const STEEP_START: f32 = 0.15;
+const STEEP_END: f32 = 0.45;
+
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct FloorSplat {
+    #[uniform(100)]
+    pub splat: FloorSplatUniform,
+    #[texture(101, dimension = "2d_array")]
+    #[sampler(102)]
+    pub layers: Handle<Image>,
+}
+
+impl MaterialExtension for FloorSplat {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/floor.wgsl".into()
+    }
+}
+
+/// Every floor texture the regions use, stacked into one array texture so a
+/// single material can blend any of them.
+#[derive(Resource, Default)]
+pub struct FloorPalette {
+    paths: Vec<String>,
+    handles: Vec<Handle<Image>>,
+    array: Option<Handle<Image>>,
+}
+
+impl FloorPalette {
+    fn layer(&self, path: &str) -> u32 {
+        self.paths.iter().position(|p| p == path).unwrap_or(0) as u32
+    }
+
+    /// Material for a chunk whose weight slots hold these regions.
+    pub fn material(&self, region_sampler: &RegionSampler, slots: [usize; 4]) -> FloorMaterial {
+        let mut splat = FloorSplatUniform {
+            steep_start: STEEP_START,
+            steep_end: STEEP_END,
+            ..default()
+        };
+        for (i, &region) in slots.iter().enumerate() {
+            let floor = &region_sampler.regions[region].floor;
+            splat.flat_layer[i] = self.layer(&floor.flat.texture);
+            splat.steep_layer[i] = self.layer(&floor.steep.texture);
+            splat.flat_tint[i] = floor.flat.tint.to_linear().to_vec4();
+            splat.steep_tint[i] = floor.steep.tint.to_linear().to_vec4();
+            splat.flat_scale[i] = floor.flat.scale;
+            splat.steep_scale[i] = floor.steep.scale;
+        }
+
+        ExtendedMaterial {
+            base: StandardMaterial {
+                alpha_mode: AlphaMode::Opaque,
+                unlit: false,
+                perceptual_roughness: 0.95,
+                metallic: 0.6,
+                ..default()
+            },
+            extension: FloorSplat {
+                splat,
+                layers: self.array.clone().unwrap_or_default(),
+            },
+        }
+    }
+}
+
+/// Run condition: the floor texture array is built for the current regions.
+pub fn floor_palette_ready(palette: Res<FloorPalette>) -> bool {
+    palette.array.is_some()
+}
+
+/// (Re)start loading floor textures whenever the regions change.
+pub fn load_floor_textures(
+    asset_server: Res<AssetServer>,
+    region_sampler: Res<RegionSampler>,
+    mut palette: ResMut<FloorPalette>,
+) {
+    if !region_sampler.is_changed() {
+        return;
+    }
+
+    let mut paths: Vec<String> = Vec::new();
+    for region in &region_sampler.regions {
+        for layer in [&region.floor.flat, &region.floor.steep] {
+            if !paths.contains(&layer.texture) {
+                paths.push(layer.texture.clone());
+            }
+        }
+    }
+    if paths == palette.paths && palette.array.is_some() {
+        return;
+    }
+
+    palette.handles = paths.iter().map(|p| asset_server.load(p.clone())).collect();
+    palette.paths = paths;
+    palette.array = None;
+}
+
+/// Once every floor texture has loaded (or failed), copy them into layers
+/// of one array texture. Layers are converted and resized to match the
+/// first texture; any that failed to load are left plain white.
+fn build_floor_array(
+    asset_server: Res<AssetServer>,
+    mut palette: ResMut<FloorPalette>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if palette.array.is_some() {
+        return;
+    }
+    let loading = palette.handles.iter().any(|h| {
+        !asset_server.is_loaded(h) && !asset_server.load_state(h).is_failed()
+    });
+    if loading {
+        return;
+    }
+
+    let layers: Vec<Option<image::DynamicImage>> = palette
+        .handles
+        .iter()
+        .zip(&palette.paths)
+        .map(|(handle, path)| {
+            let image = images.get(handle)?;
+            match image.clone().try_into_dynamic() {
+                Ok(image) => Some(image),
+                Err(err) => {
+                    warn!("Floor texture {path} can't be used: {err}");
+                    None
+                }
+            }
+        })
+        .collect();
+    for (layer, path) in layers.iter().zip(&palette.paths) {
+        if layer.is_none() {
+            warn!("Floor texture {path} didn't load, painting it white");
+        }
+    }
+
+    let (width, height) = layers
+        .iter()
+        .flatten()
+        .next()
+        .map(|image| (image.width(), image.height()))
+        .unwrap_or((1, 1));
+    let mut data = Vec::with_capacity((width * height * 4) as usize * layers.len());
+    for layer in &layers {
+        match layer {
+            Some(image) => data.extend(
+                image
+                    .resize_exact(width, height, image::imageops::FilterType::Triangle)
+                    .into_rgba8()
+                    .into_raw(),
+            ),
+            None => data.extend(std::iter::repeat_n(255, (width * height * 4) as usize)),
+        }
+    }
+
+    let mut array = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers.len().max(1) as u32,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    // floors are tiled in world space
+    array.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+    // a single layer would otherwise get a plain 2d view, which the shader's
+    // texture_2d_array binding won't take
+    array.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    palette.array = Some(images.add(array));
+}
+
+/// Paint region blend weights and slope onto a chunk mesh, for the floor
+/// shader. Up to four regions get a weight slot (heaviest first); the
+/// returned region indices say which. Weights ride in the vertex colour and
+/// slope in the second UV set, so the stock PBR vertex shader carries them
+/// through untouched.
+pub fn splat_attributes(
+    mesh: &mut Mesh,
+    region_sampler: &RegionSampler,
+    world_offset: Vec2,
+) -> [usize; 4] {
+    let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return [0; 4];
+    };
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(normals)) => normals.clone(),
+        _ => vec![[0.0, 1.0, 0.0]; positions.len()],
+    };
+
+    let samples: Vec<_> = positions
+        .iter()
+        .map(|p| region_sampler.sample_region(world_offset + Vec2::new(p[0], p[2])))
+        .collect();
+
+    // heaviest regions across the whole chunk get the slots
+    let mut totals: HashMap<usize, f32> = HashMap::new();
+    for (ids, weights) in &samples {
+        for (id, w) in ids.iter().zip(weights) {
+            *totals.entry(*id).or_default() += w;
+        }
+    }
+    let mut ranked: Vec<(usize, f32)> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut slots = [ranked.first().map_or(0, |r| r.0); 4];
+    for (slot, (id, _)) in slots.iter_mut().zip(&ranked) {
+        *slot = *id;
+    }
+    let used = ranked.len().min(4);
+
+    let colours: Vec<[f32; 4]> = samples
+        .iter()
+        .map(|(ids, weights)| {
+            let mut out = [0.0; 4];
+            for (id, w) in ids.iter().zip(weights) {
+                if let Some(slot) = slots[..used].iter().position(|s| s == id) {
+                    out[slot] += w;
+                }
+            }
+            let sum: f32 = out.iter().sum();
+            if sum > 0.0 {
+                out.map(|w| w / sum)
+            } else {
+                [1.0, 0.0, 0.0, 0.0]
+            }
+        })
+        .collect();
+    let slopes: Vec<[f32; 2]> = normals.iter().map(|n| [1.0 - n[1], 0.0]).collect();
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colours);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_1, slopes);
+    slots
+}
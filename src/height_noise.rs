@@ -1,33 +1,320 @@
+use crate::stable_hash::StableHasher;
 use bevy::prelude::Resource;
-use noise::core::perlin::perlin_2d;
-use noise::{NoiseFn, Perlin};
+use noise::{NoiseFn, Perlin, Seedable};
 
-/// Resource wrapping a shared Perlin noise generator
+/// A height function, built as a small tree of noise and shaping nodes.
+/// Regions describe theirs in the regions file, so new shapes are data
+/// rather than new structs.
 #[derive(Resource)]
 #[derive(Clone, Debug)]
-pub struct HeightNoise {
-    pub perlin_height: f64,
-    pub perlin_scale: f64,
-    pub perlin: Perlin,
-    pub terrace_height: f64,
-    pub terrace_steps: f64,
-    pub terrace_scale: f64,
-    pub terrace_source: Perlin,
-    pub terrace_smooth_width :f32,
-    pub offset:f64,
+pub enum HeightNoise {
+    Constant(f64),
+    /// Plain Perlin, in -height..height.
+    Perlin {
+        perlin: Perlin,
+        scale: f64,
+        height: f64,
+    },
+    /// Octaves of Perlin summed with falling amplitude, in roughly -height..height.
+    Fbm {
+        perlin: Perlin,
+        scale: f64,
+        height: f64,
+        octaves: u32,
+        lacunarity: f64,
+        persistence: f64,
+    },
+    /// Like fBm but folded into sharp crests, in 0..height.
+    Ridged {
+        perlin: Perlin,
+        scale: f64,
+        height: f64,
+        octaves: u32,
+        lacunarity: f64,
+        persistence: f64,
+    },
+    /// Samples `input` at a point pushed around by another noise field.
+    Warp {
+        input: Box<HeightNoise>,
+        perlin: Perlin,
+        scale: f64,
+        strength: f64,
+    },
+    /// `smooth_terrace` over `input`, with `steps` levels per unit.
+    Terrace {
+        input: Box<HeightNoise>,
+        steps: f64,
+        smooth_width: f32,
+        height: f64,
+    },
+    /// Piecewise-linear curve through `points` (ascending x), flat past the ends.
+    Curve {
+        input: Box<HeightNoise>,
+        points: Vec<(f64, f64)>,
+    },
+    /// Linear remap of `from` onto `to`, not clamped.
+    Remap {
+        input: Box<HeightNoise>,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    Clamp {
+        input: Box<HeightNoise>,
+        min: f64,
+        max: f64,
+    },
+    Sum(Vec<HeightNoise>),
+    Product(Vec<HeightNoise>),
+    Min(Vec<HeightNoise>),
+    Max(Vec<HeightNoise>),
 }
 
 impl HeightNoise {
     pub fn sample(&self, point: [f64; 2]) -> f64 {
-        let perlin_point_scaled = point.map(|coord| coord * self.perlin_scale);
-        let perlin_val = self.perlin_height *(self.perlin.get(perlin_point_scaled));
+        match self {
+            HeightNoise::Constant(value) => *value,
+            HeightNoise::Perlin {
+                perlin,
+                scale,
+                height,
+            } => height * perlin.get(point.map(|coord| coord * scale)),
+            HeightNoise::Fbm {
+                perlin,
+                scale,
+                height,
+                octaves,
+                lacunarity,
+                persistence,
+            } => {
+                height
+                    * octave_sum(point, *scale, *octaves, *lacunarity, *persistence, |p| {
+                        perlin.get(p)
+                    })
+            }
+            HeightNoise::Ridged {
+                perlin,
+                scale,
+                height,
+                octaves,
+                lacunarity,
+                persistence,
+            } => {
+                height
+                    * octave_sum(point, *scale, *octaves, *lacunarity, *persistence, |p| {
+                        let ridge = 1.0 - perlin.get(p).abs();
+                        ridge * ridge
+                    })
+            }
+            HeightNoise::Warp {
+                input,
+                perlin,
+                scale,
+                strength,
+            } => {
+                // x and z offsets read well-separated parts of the same field
+                let [x, z] = point.map(|coord| coord * scale);
+                input.sample([
+                    point[0] + perlin.get([x, z]) * strength,
+                    point[1] + perlin.get([x + 71.3, z - 37.9]) * strength,
+                ])
+            }
+            HeightNoise::Terrace {
+                input,
+                steps,
+                smooth_width,
+                height,
+            } => {
+                let value = input.sample(point);
+                smooth_terrace((value * steps) as f32, *smooth_width) as f64 / steps * height
+            }
+            HeightNoise::Curve { input, points } => curve(input.sample(point), points),
+            HeightNoise::Remap { input, from, to } => {
+                let t = (input.sample(point) - from.0) / (from.1 - from.0);
+                to.0 + (to.1 - to.0) * t
+            }
+            HeightNoise::Clamp { input, min, max } => input.sample(point).clamp(*min, *max),
+            HeightNoise::Sum(inputs) => inputs.iter().map(|n| n.sample(point)).sum(),
+            HeightNoise::Product(inputs) => inputs.iter().map(|n| n.sample(point)).product(),
+            HeightNoise::Min(inputs) => inputs
+                .iter()
+                .map(|n| n.sample(point))
+                .fold(f64::INFINITY, f64::min),
+            HeightNoise::Max(inputs) => inputs
+                .iter()
+                .map(|n| n.sample(point))
+                .fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl HeightNoise {
+    /// Feed the whole graph to `hasher`, Perlin fields by their seed.
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        let nodes = |hasher: &mut StableHasher, inputs: &[HeightNoise]| {
+            hasher.u64(inputs.len() as u64);
+            for input in inputs {
+                input.hash_into(hasher);
+            }
+        };
+        match self {
+            HeightNoise::Constant(value) => {
+                hasher.u32(0).f64(*value);
+            }
+            HeightNoise::Perlin {
+                perlin,
+                scale,
+                height,
+            } => {
+                hasher.u32(1).u32(perlin.seed()).f64(*scale).f64(*height);
+            }
+            HeightNoise::Fbm {
+                perlin,
+                scale,
+                height,
+                octaves,
+                lacunarity,
+                persistence,
+            } => {
+                hasher
+                    .u32(2)
+                    .u32(perlin.seed())
+                    .f64(*scale)
+                    .f64(*height)
+                    .u32(*octaves)
+                    .f64(*lacunarity)
+                    .f64(*persistence);
+            }
+            HeightNoise::Ridged {
+                perlin,
+                scale,
+                height,
+                octaves,
+                lacunarity,
+                persistence,
+            } => {
+                hasher
+                    .u32(3)
+                    .u32(perlin.seed())
+                    .f64(*scale)
+                    .f64(*height)
+                    .u32(*octaves)
+                    .f64(*lacunarity)
+                    .f64(*persistence);
+            }
+            HeightNoise::Warp {
+                input,
+                perlin,
+                scale,
+                strength,
+            } => {
+                hasher.u32(4).u32(perlin.seed()).f64(*scale).f64(*strength);
+                input.hash_into(hasher);
+            }
+            HeightNoise::Terrace {
+                input,
+                steps,
+                smooth_width,
+                height,
+            } => {
+                hasher.u32(5).f64(*steps).f32(*smooth_width).f64(*height);
+                input.hash_into(hasher);
+            }
+            HeightNoise::Curve { input, points } => {
+                hasher.u32(6).u64(points.len() as u64);
+                for &(x, y) in points {
+                    hasher.f64(x).f64(y);
+                }
+                input.hash_into(hasher);
+            }
+            HeightNoise::Remap { input, from, to } => {
+                hasher.u32(7).f64(from.0).f64(from.1).f64(to.0).f64(to.1);
+                input.hash_into(hasher);
+            }
+            HeightNoise::Clamp { input, min, max } => {
+                hasher.u32(8).f64(*min).f64(*max);
+                input.hash_into(hasher);
+            }
+            HeightNoise::Sum(inputs) => nodes(hasher.u32(9), inputs),
+            HeightNoise::Product(inputs) => nodes(hasher.u32(10), inputs),
+            HeightNoise::Min(inputs) => nodes(hasher.u32(11), inputs),
+            HeightNoise::Max(inputs) => nodes(hasher.u32(12), inputs),
+        }
+    }
+}
+
+/// Octaves of `noise` at doubling (by `lacunarity`) frequency and falling
+/// (by `persistence`) amplitude, normalised so the amplitudes sum to 1.
+fn octave_sum<const N: usize>(
+    point: [f64; N],
+    scale: f64,
+    octaves: u32,
+    lacunarity: f64,
+    persistence: f64,
+    noise: impl Fn([f64; N]) -> f64,
+) -> f64 {
+    let mut frequency = scale;
+    let mut amplitude = 1.0;
+    let mut total = 0.0;
+    let mut amplitude_sum = 0.0;
+    for octave in 0..octaves {
+        // nudge each octave so their lattices don't line up at the origin
+        let shift = octave as f64 * 17.31;
+        total += noise(point.map(|coord| coord * frequency + shift)) * amplitude;
+        amplitude_sum += amplitude;
+        frequency *= lacunarity;
+        amplitude *= persistence;
+    }
+    if amplitude_sum > 0.0 {
+        total / amplitude_sum
+    } else {
+        0.0
+    }
+}
+
+/// 3D noise added to a region's heightfield density, so its seabed can
+/// overhang and hollow out into caves. See `RegionSampler::sample_density`.
+#[derive(Clone, Debug)]
+pub struct CaveField {
+    pub perlin: Perlin,
+    pub scale: f64,
+    /// Furthest (in world units) the caves reach above or below the heightfield.
+    pub strength: f32,
+    pub octaves: u32,
+}
 
-        let terrace_point_scaled = point.map(|coord| coord * self.terrace_scale);
-        let mut terrace_val = (self.terrace_source.get(terrace_point_scaled));
-        terrace_val = smooth_terrace ((terrace_val * self.terrace_steps) as f32, self.terrace_smooth_width) as f64 / self.terrace_steps * self.terrace_height;
-        perlin_val + terrace_val + self.offset
+impl CaveField {
+    /// In -strength..strength; positive adds rock, negative carves it away.
+    pub fn sample(&self, point: [f64; 3]) -> f64 {
+        self.strength as f64 * octave_sum(point, self.scale, self.octaves, 2.0, 0.5, |p| self.perlin.get(p))
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        hasher
+            .u32(self.perlin.seed())
+            .f64(self.scale)
+            .f32(self.strength)
+            .u32(self.octaves);
     }
 }
+
+/// Piecewise-linear lookup through `points`, which must have ascending x.
+fn curve(x: f64, points: &[(f64, f64)]) -> f64 {
+    let Some(&(first_x, first_y)) = points.first() else {
+        return x;
+    };
+    if x <= first_x {
+        return first_y;
+    }
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if x <= x1 {
+            let t = if x1 > x0 { (x - x0) / (x1 - x0) } else { 1.0 };
+            return y0 + (y1 - y0) * t;
+        }
+    }
+    points[points.len() - 1].1
+}
+
 /// Soft‐terrace a float so it “snaps” at integer steps
 /// with a smooth ramp of width `w` (0.0 → hard terrace,
 /// 1.0 → a full-step linear ramp).
@@ -39,6 +326,10 @@ pub fn smooth_terrace(x: f32, w: f32) -> f32 {
     let i = x.floor();
     // fractional [0..1)
     let f = x - i;
+    // no ramp at all: jump halfway through the step
+    if w <= 0.0 {
+        return if f < 0.5 { i } else { i + 1.0 };
+    }
     // center the ramp around 0.5
     let lo = 0.5 - 0.5 * w;
     let hi = 0.5 + 0.5 * w;
@@ -50,3 +341,87 @@ pub fn smooth_terrace(x: f32, w: f32) -> f32 {
     // put us back on the “terraced” curve
     i + s
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remap(value: f64) -> f64 {
+        HeightNoise::Remap {
+            input: Box::new(HeightNoise::Constant(value)),
+            from: (-1.0, 1.0),
+            to: (2.0, 6.0),
+        }
+        .sample([0.0, 0.0])
+    }
+
+    #[test]
+    fn curve_is_flat_past_both_ends() {
+        let points = [(-1.0, 2.0), (0.0, 0.0), (1.0, 5.0)];
+        assert_eq!(curve(-10.0, &points), 2.0);
+        assert_eq!(curve(-1.0, &points), 2.0);
+        assert_eq!(curve(0.5, &points), 2.5);
+        assert_eq!(curve(1.0, &points), 5.0);
+        assert_eq!(curve(10.0, &points), 5.0);
+    }
+
+    #[test]
+    fn curve_steps_at_duplicate_x() {
+        let points = [(0.0, 0.0), (1.0, 1.0), (1.0, 3.0), (2.0, 3.0)];
+        assert_eq!(curve(0.5, &points), 0.5);
+        assert_eq!(curve(1.0, &points), 1.0);
+        assert_eq!(curve(1.0 + 1e-9, &points), 3.0);
+        assert_eq!(curve(1.5, &points), 3.0);
+    }
+
+    #[test]
+    fn remap_maps_its_endpoints() {
+        assert_eq!(remap(-1.0), 2.0);
+        assert_eq!(remap(1.0), 6.0);
+        assert_eq!(remap(0.0), 4.0);
+        // not clamped
+        assert_eq!(remap(3.0), 10.0);
+    }
+
+    #[test]
+    fn clamp_holds_its_input_in_range() {
+        let clamp = |value| {
+            HeightNoise::Clamp {
+                input: Box::new(HeightNoise::Constant(value)),
+                min: -1.0,
+                max: 2.0,
+            }
+            .sample([0.0, 0.0])
+        };
+        assert_eq!(clamp(-5.0), -1.0);
+        assert_eq!(clamp(0.5), 0.5);
+        assert_eq!(clamp(5.0), 2.0);
+    }
+
+    #[test]
+    fn octave_sum_is_normalised() {
+        for octaves in 1..6 {
+            let sum = octave_sum([0.3, 0.7], 0.1, octaves, 2.0, 0.5, |_| 1.0);
+            assert!((sum - 1.0).abs() < 1e-12, "{octaves} octaves: {sum}");
+        }
+        assert_eq!(octave_sum([0.3, 0.7], 0.1, 0, 2.0, 0.5, |_| 1.0), 0.0);
+    }
+
+    #[test]
+    fn terrace_stays_finite_and_rises_between_levels() {
+        for w in [0.0, 0.2, 1.0] {
+            let mut last = f32::NEG_INFINITY;
+            for k in 0..=40 {
+                let x = k as f32 * 0.05;
+                let y = smooth_terrace(x, w);
+                assert!(y.is_finite(), "w {w}: {x} gave {y}");
+                assert!(y >= last, "w {w}: falls at {x}");
+                assert!((x.floor()..=x.floor() + 1.0).contains(&y), "w {w}: {x} gave {y}");
+                last = y;
+            }
+            assert_eq!(smooth_terrace(1.0, w), 1.0);
+        }
+        assert_eq!(smooth_terrace(0.49, 0.0), 0.0);
+        assert_eq!(smooth_terrace(0.5, 0.0), 1.0);
+    }
+}
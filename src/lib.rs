@@ -0,0 +1,12 @@
+//! World generation shared by the game and the `worldmap` tool: region
+//! definitions, the height graph and the sampler that blends them, and the
+//! object catalogue asset packs extend. Also the damping helpers the game
+//! smooths motion with.
+
+pub mod height_noise;
+pub mod object_manager;
+pub mod placement;
+pub mod region_assets;
+pub mod region_sampler;
+pub mod smooth_math;
+mod stable_hash;
@@ -1,34 +1,48 @@
+mod behaviour;
 mod camera;
+mod chunk_diagnostics;
 mod chunked_env;
+mod coral_planting;
+mod density_mesh;
 mod env_manager;
+mod fauna;
 mod fishy;
-mod height_noise;
-mod object_manager;
-mod region_sampler;
-mod smooth_math;
+mod floor_material;
+mod marine_snow;
+mod reef_health;
+mod scatter;
+mod shader_uniforms;
 mod turtle_model;
+mod world_edits;
 
+// world generation lives in the library, shared with the worldmap tool
+use karang_lestari::{object_manager, placement, region_assets, region_sampler, smooth_math};
+
+use crate::behaviour::BehaviourPlugin;
 use crate::camera::components::FollowTarget;
 use crate::camera::plugin::OrbitCameraPlugin;
 use crate::camera::systems::smooth_follow;
+use crate::chunk_diagnostics::ChunkDiagnosticsPlugin;
 use crate::chunked_env::ChunkedEnvironmentPlugin;
+use crate::coral_planting::CoralPlantingPlugin;
 use crate::env_manager::{EnvManagerPlugin, MainLight, SecondaryLight};
+use crate::fauna::FaunaPlugin;
 use crate::fishy::{fish_movement_system, FishMovement};
-use crate::height_noise::HeightNoise;
+use crate::floor_material::FloorMaterialPlugin;
+use crate::marine_snow::MarineSnowPlugin;
 use crate::object_manager::ObjectManagerPlugin;
-use crate::region_sampler::{LightingSetup, ObjectSelection, Region, RegionSampler};
+use crate::reef_health::ReefHealthPlugin;
+use crate::region_assets::RegionsPlugin;
+use crate::region_sampler::RegionSampler;
 use crate::turtle_model::TurtlePlugin;
+use crate::world_edits::WorldEditsPlugin;
 use bevy::asset::AssetMetaCheck;
 use bevy::prelude::*;
 use std::f32::consts::PI;
+use std::time::Duration;
 use bevy::window::WindowTheme;
 
 fn main() {
-    let standard_lights: Vec<LightingSetup> = get_standard_lights();
-
-    let common_objects = get_common_objects();
-    let human_objects = get_human_objects();
-
     App::new()
         .add_plugins(DefaultPlugins.set(AssetPlugin {
             // Wasm builds will check for meta files (that don't exist) if this isn't set.
@@ -54,398 +68,38 @@ fn main() {
             Startup,
             (setup_env, setup_player).before(camera::systems::spawn_camera_rig),
         )
-        .add_plugins(ObjectManagerPlugin::default())
-        .add_plugins(TurtlePlugin::default())
-        .add_plugins(OrbitCameraPlugin::default())
-        .add_plugins(EnvManagerPlugin::default())
-        .add_systems(FixedUpdate, fish_movement_system.before(smooth_follow))
+        .add_plugins(ObjectManagerPlugin)
+        .add_plugins(TurtlePlugin)
+        .add_plugins(OrbitCameraPlugin)
+        .add_plugins(EnvManagerPlugin)
+        .add_systems(
+            FixedUpdate,
+            fish_movement_system
+                .before(smooth_follow)
+                .run_if(resource_exists::<RegionSampler>),
+        )
         .insert_resource(TitleResource {
             showing: true,
             alpha: 0.0,
         })
         .add_systems(Update, title_system)
         .insert_resource(ClearColor(Color::srgb(0.2, 0.71, 0.75)))
-        .insert_resource(RegionSampler::new(
-            vec![
-                Region::new(
-                    "Smooth Sandbanks".into(),
-                    20,
-                    HeightNoise {
-                        perlin_height: 1.0,
-                        perlin_scale: 0.1,
-                        perlin: Default::default(),
-                        terrace_height: 2.0,
-                        terrace_steps: 4.0,
-                        terrace_scale: 0.02,
-                        terrace_source: Default::default(),
-                        terrace_smooth_width: 0.1,
-                        offset: 0.0,
-                    },
-                    common_objects.clone(),
-                    standard_lights.clone(),
-                ),
-                Region::new(
-                    "Lil Cliffs".into(),
-                    10,
-                    HeightNoise {
-                        perlin_height: 1.0,
-                        perlin_scale: 0.1,
-                        perlin: Default::default(),
-                        terrace_height: 15.0,
-                        terrace_steps: 3.0,
-                        terrace_scale: 0.01,
-                        terrace_source: Default::default(),
-                        terrace_smooth_width: 0.2,
-                        offset: 0.0,
-                    },
-                    common_objects.clone(),
-                    standard_lights.clone(),
-                ),
-                Region::new(
-                    "Restoration Zone".into(),
-                    5,
-                    HeightNoise {
-                        perlin_height: 1.0,
-                        perlin_scale: 0.1,
-                        perlin: Default::default(),
-                        terrace_height: 2.0,
-                        terrace_steps: 3.0,
-                        terrace_scale: 0.02,
-                        terrace_source: Default::default(),
-                        terrace_smooth_width: 0.1,
-                        offset: 0.0,
-                    },
-                    [common_objects, human_objects].concat(),
-                    standard_lights.clone(),
-                ),
-                Region::new(
-                    "Big Cliffs".into(),
-                    1,
-                    HeightNoise {
-                        perlin_height: 1.0,
-                        perlin_scale: 0.1,
-                        perlin: Default::default(),
-                        terrace_height: 55.0,
-                        terrace_steps: 4.0,
-                        terrace_scale: 0.02,
-                        terrace_source: Default::default(),
-                        terrace_smooth_width: 0.2,
-                        offset: -30.0,
-                    },
-                    Vec::from([
-                        ObjectSelection {
-                            name: "acropora_cytherea_2_komang".into(),
-                            selection_weight: 1,
-                        },
-                        ObjectSelection {
-                            name: "tendrils".into(),
-                            selection_weight: 10,
-                        },
-                        ObjectSelection {
-                            name: "small_yellow_coral_paula".into(),
-                            selection_weight: 3,
-                        },
-                    ]),
-                    vec![
-                        LightingSetup {
-                            name: "Depths".into(),
-                            primary_color: Color::srgb(0.97, 0.5, 0.8),
-                            primary_illuminance: 2_000.,
-                            secondary_color: Color::srgb(0.75, 0.7, 1.0),
-                            secondary_illuminance: 2_000.,
-                            time: 0.0,
-                            fog_colour: Color::srgba(0.0, 0.15, 0.1, 1.0),
-                            clear_colour: Color::srgb(0.0, 0.15, 0.1),
-                        },
-                        LightingSetup {
-                            name: "Depths Wrap".into(),
-                            primary_color: Color::srgb(0.97, 0.5, 0.8),
-                            primary_illuminance: 2_000.,
-                            secondary_color: Color::srgb(0.75, 0.7, 1.0),
-                            secondary_illuminance: 2_000.,
-                            time: 1.0,
-                            fog_colour: Color::srgba(0.0, 0.15, 0.1, 1.0),
-                            clear_colour: Color::srgb(0.0, 0.15, 0.1),
-                        },
-                    ],
-                ),
-            ],
-            100.0,
-            0.3,
-            10.0,
-            42,
-        ))
+        .add_plugins(RegionsPlugin::default())
+        .add_plugins(FloorMaterialPlugin)
+        .add_plugins(WorldEditsPlugin::default())
+        .add_plugins(CoralPlantingPlugin)
+        .add_plugins(ReefHealthPlugin)
+        .add_plugins(MarineSnowPlugin::default())
+        .add_plugins(BehaviourPlugin)
+        .add_plugins(FaunaPlugin::default())
         .add_plugins(ChunkedEnvironmentPlugin)
+        .add_plugins(ChunkDiagnosticsPlugin {
+            // keep an eye on asset churn while developing
+            log_every: cfg!(debug_assertions).then_some(Duration::from_secs(10)),
+        })
         .run();
 }
 
-fn get_standard_lights() -> Vec<LightingSetup> {
-    vec![
-        LightingSetup {
-            name: "Sunset Wrap".into(),
-            primary_color: Color::srgb(0.9, 0.4, 0.7),
-            primary_illuminance: 3_000.,
-            secondary_color: Color::srgb(0.8, 0.5, 0.95),
-            secondary_illuminance: 1_000.,
-            time: 0.0,
-            fog_colour: Color::srgba(0.7, 0.3, 0.1, 1.0),
-            clear_colour: Color::srgb(0.8, 0.4, 0.15),
-        },
-        LightingSetup {
-            name: "Early Night".into(),
-            primary_color: Color::srgb(0.97, 0.5, 0.8),
-            primary_illuminance: 500.,
-            secondary_color: Color::srgb(0.75, 0.7, 1.0),
-            secondary_illuminance: 2_000.,
-            time: 0.1,
-            fog_colour: Color::srgba(0.2, 0.1, 0.3, 1.0),
-            clear_colour: Color::srgb(0.2, 0.15, 0.2),
-        },
-        LightingSetup {
-            name: "Early Morning".into(),
-            primary_color: Color::srgb(0.97, 0.7, 0.5),
-            primary_illuminance: 500.,
-            secondary_color: Color::srgb(0.75, 0.7, 0.95),
-            secondary_illuminance: 2_000.,
-            time: 0.4,
-            fog_colour: Color::srgba(0.3, 0.1, 0.2, 1.0),
-            clear_colour: Color::srgb(0.2, 0.15, 0.2),
-        },
-        LightingSetup {
-            name: "Sunrise".into(),
-            primary_color: Color::srgb(0.9, 0.5, 0.2),
-            primary_illuminance: 3_000.,
-            secondary_color: Color::srgb(0.8, 0.5, 0.95),
-            secondary_illuminance: 2_000.,
-            time: 0.5,
-            fog_colour: Color::srgba(0.7, 0.2, 0.4, 1.0),
-            clear_colour: Color::srgb(0.8, 0.3, 0.5),
-        },
-        LightingSetup {
-            name: "Early Morning".into(),
-            primary_color: Color::srgb(0.97, 0.94, 0.7),
-            primary_illuminance: 6_000.,
-            secondary_color: Color::srgb(0.97, 0.8, 0.8),
-            secondary_illuminance: 2_000.,
-            time: 0.6,
-            fog_colour: Color::srgba(0.0, 0.6, 0.80, 1.0),
-            clear_colour: Color::srgb(0.1, 0.5, 0.75),
-        },
-        LightingSetup {
-            name: "Noon".into(),
-            primary_color: Color::srgb(0.97, 0.94, 1.0),
-            primary_illuminance: 7_000.,
-            secondary_color: Color::srgb(0.97, 0.8, 0.8),
-            secondary_illuminance: 3_000.,
-            time: 0.75,
-            fog_colour: Color::srgba(0.0, 0.85, 0.90, 1.0),
-            clear_colour: Color::srgb(0.2, 0.71, 0.75),
-        },
-        LightingSetup {
-            name: "Late Afternoon".into(),
-            primary_color: Color::srgb(0.9, 0.8, 0.9),
-            primary_illuminance: 6_000.,
-            secondary_color: Color::srgb(0.8, 0.5, 0.95),
-            secondary_illuminance: 2_000.,
-            time: 0.9,
-            fog_colour: Color::srgba(0.0, 0.85, 0.95, 1.0),
-            clear_colour: Color::srgb(0.2, 0.71, 0.80),
-        },
-        LightingSetup {
-            name: "Sunset".into(),
-            primary_color: Color::srgb(0.9, 0.4, 0.7),
-            primary_illuminance: 3_000.,
-            secondary_color: Color::srgb(0.8, 0.5, 0.95),
-            secondary_illuminance: 1_000.,
-            time: 1.0,
-            fog_colour: Color::srgba(0.7, 0.3, 0.1, 1.0),
-            clear_colour: Color::srgb(0.8, 0.4, 0.15),
-        },
-    ]
-}
-
-fn get_common_objects() -> Vec<ObjectSelection> {
-    Vec::from([
-        ObjectSelection {
-            name: "acropora_3_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_4_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_abrolhosensis_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_cytherea_2_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_cytherea_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_cytherea_pink_2_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_cytherea_pink_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_natalensis_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "acropora_sukarnoi_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "big_sponge_paula".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "diploastrea_heliopora_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "favia_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "leptoria_phrygia_2_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "leptoria_phrygia_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "lobophytum_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "mixed_coral_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "montipora_digitata_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "pincushion_starfish_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "pocillopora_meandrina_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_2_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_3_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_4_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_5_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_6_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_7_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "porites_lutea_anten_q".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "small_yellow_coral_paula".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "sponge_4_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "sponge_5_anten".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "sponge_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "starfish_2_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "starfish_komang".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "tendrils".into(),
-            selection_weight: 5,
-        },
-        ObjectSelection {
-            name: "turbinaria_2_anten".into(),
-            selection_weight: 2,
-        },
-        ObjectSelection {
-            name: "turbinaria_photos_komang".into(),
-            selection_weight: 2,
-        },
-    ])
-}
-
-fn get_human_objects() -> Vec<ObjectSelection> {
-    Vec::from([
-        ObjectSelection {
-            name: "block_arch_paula".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "concrete_dome_anten".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "concrete_turtle_anten".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "concrete_turtle_komang".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "coral_table_komang".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "les_komang".into(),
-            selection_weight: 1,
-        },
-        ObjectSelection {
-            name: "tunnel_komang".into(),
-            selection_weight: 1,
-        },
-    ])
-}
-
 fn setup_player(mut commands: Commands, asset_server: Res<AssetServer>) {
     // 1. Load the GLTF scene handle
     let turtle_scene =
@@ -510,7 +164,7 @@ fn setup_player(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
     commands.spawn((
         // Accepts a `String` or any type that converts into a `String`, such as `&str`
-        Text::new("Movement: WASD or left stick\nUp/down: Q/E or X/O buttons\nCamera: mouse or right stick\nFaster (hold): space or R2\nQuit: Esc"),
+        Text::new("Movement: WASD or left stick\nUp/down: Q/E or X/O buttons\nCamera: mouse or right stick\nFaster (hold): space or R2\nTake/plant coral: F or square button\nQuit: Esc"),
         TextFont {
             // This font is loaded and will be used instead of the default font.
             font: asset_server.load("fonts/FiraMono-Medium.ttf"),
@@ -612,7 +266,7 @@ fn title_system(
     kb: Res<ButtonInput<KeyCode>>,
     mut title_resource: ResMut<TitleResource>,
     mut query: Query<(&mut TextColor, &mut TextShadow)>,
-    mut movement_query: Query<(&mut FishMovement)>,
+    movement_query: Query<&FishMovement, With<FollowTarget>>,
     mut exit: EventWriter<AppExit>,
 ) {
     if !title_resource.showing && kb.just_pressed(KeyCode::Escape) {
@@ -622,7 +276,7 @@ fn title_system(
     }
 
     let mut should_fade = false;
-    for (mover) in movement_query.iter() {
+    for mover in movement_query.iter() {
         if mover.current_go_force > 0.0 {
             should_fade = true;
         }
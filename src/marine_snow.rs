@@ -0,0 +1,218 @@
+use crate::env_manager::EnvManager;
+use crate::region_sampler::RegionSampler;
+use crate::shader_uniforms::MarineSnowUniform;
+use bevy::asset::RenderAssetUsages;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey, NotShadowCaster};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, MeshVertexBufferLayoutRef, PrimitiveTopology};
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+};
+use bevy::render::view::NoFrustumCulling;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Marine snow and plankton: specks drifting with the current through a
+/// cube of water that follows the camera, wrapping round as they leave it.
+/// All of them are one mesh, moved in the vertex shader, so there can be
+/// thousands. How many show, and their colour, comes from the regions
+/// around the camera and the time of day.
+///
+/// Call `.add_plugins(MarineSnowPlugin::default())` in your App.
+pub struct MarineSnowPlugin {
+    /// Most specks there can be; a region's density says how many show.
+    pub count: u32,
+    /// Side of the cube of water they fill, centred on the camera.
+    pub volume: f32,
+    /// Radius of an average speck.
+    pub size: f32,
+    /// Which way, and how fast (units a second), the water drifts.
+    pub current: Vec3,
+}
+
+impl Default for MarineSnowPlugin {
+    fn default() -> Self {
+        MarineSnowPlugin {
+            count: 6000,
+            volume: 16.0,
+            size: 0.012,
+            // a gentle drift, settling slowly
+            current: Vec3::new(0.06, -0.015, 0.03),
+        }
+    }
+}
+
+impl Plugin for MarineSnowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<MarineSnowMaterial> {
+            prepass_enabled: false,
+            shadows_enabled: false,
+            ..default()
+        })
+        .insert_resource(MarineSnowSettings {
+            count: self.count,
+            volume: self.volume,
+            size: self.size,
+            current: self.current,
+        })
+        .init_resource::<MarineSnowState>()
+        .add_systems(Startup, spawn_marine_snow)
+        .add_systems(
+            Update,
+            update_marine_snow.run_if(resource_exists::<RegionSampler>),
+        );
+    }
+}
+
+/// How opaque a speck is at its centre.
+const OPACITY: f32 = 0.7;
+
+#[derive(Resource)]
+struct MarineSnowSettings {
+    count: u32,
+    volume: f32,
+    size: f32,
+    current: Vec3,
+}
+
+#[derive(Resource, Default)]
+struct MarineSnowState {
+    material: Handle<MarineSnowMaterial>,
+    /// How far the current has carried everything, wrapped to the volume.
+    drift: Vec3,
+}
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct MarineSnowMaterial {
+    #[uniform(0)]
+    pub snow: MarineSnowUniform,
+}
+
+impl Material for MarineSnowMaterial {
+    fn vertex_shader() -> ShaderRef {
+        "shaders/marine_snow.wgsl".into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        "shaders/marine_snow.wgsl".into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.0.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_UV_0.at_shader_location(1),
+            Mesh::ATTRIBUTE_UV_1.at_shader_location(2),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// One quad per speck, every corner at the speck's spot in the volume: the
+/// vertex shader wraps it round the camera and turns it to face it. uv_0 is
+/// the corner, uv_1 two random numbers per speck (whether it shows at a
+/// given density, and its size).
+fn marine_snow_mesh(count: u32, volume: f32) -> Mesh {
+    let mut rng = ChaCha8Rng::seed_from_u64(0x5eed_5a0f);
+    let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+    let mut positions = Vec::with_capacity(count as usize * 4);
+    let mut uv_0 = Vec::with_capacity(count as usize * 4);
+    let mut uv_1 = Vec::with_capacity(count as usize * 4);
+    let mut indices = Vec::with_capacity(count as usize * 6);
+    for i in 0..count {
+        let position = [
+            rng.random_range(0.0..volume),
+            rng.random_range(0.0..volume),
+            rng.random_range(0.0..volume),
+        ];
+        let random = [rng.random::<f32>(), rng.random::<f32>()];
+        for corner in corners {
+            positions.push(position);
+            uv_0.push(corner);
+            uv_1.push(random);
+        }
+        let base = i * 4;
+        indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uv_0)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_1, uv_1)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+fn spawn_marine_snow(
+    mut commands: Commands,
+    settings: Res<MarineSnowSettings>,
+    mut state: ResMut<MarineSnowState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<MarineSnowMaterial>>,
+) {
+    state.material = materials.add(MarineSnowMaterial {
+        snow: MarineSnowUniform {
+            volume: settings.volume,
+            size: settings.size,
+            ..default()
+        },
+    });
+    commands.spawn((
+        Name::new("MarineSnow"),
+        Mesh3d(meshes.add(marine_snow_mesh(settings.count, settings.volume))),
+        MeshMaterial3d(state.material.clone()),
+        Transform::default(),
+        // it's placed in the shader, so its bounds mean nothing
+        NoFrustumCulling,
+        NotShadowCaster,
+    ));
+}
+
+fn update_marine_snow(
+    time: Res<Time>,
+    settings: Res<MarineSnowSettings>,
+    mut state: ResMut<MarineSnowState>,
+    env_manager: Res<EnvManager>,
+    region_sampler: Res<RegionSampler>,
+    camera: Query<&GlobalTransform, With<Camera3d>>,
+    mut materials: ResMut<Assets<MarineSnowMaterial>>,
+) {
+    let Ok(cam_t) = camera.single() else {
+        return;
+    };
+    state.drift = (state.drift + settings.current * time.delta_secs())
+        .rem_euclid(Vec3::splat(settings.volume));
+
+    // blend the regions around the camera, as the lighting does
+    let (regions, weights) = region_sampler.sample_region(cam_t.translation().xz());
+    let daylight = env_manager.daylight();
+    let mut density = 0.0;
+    let mut colour = Vec4::ZERO;
+    for (&r, w) in regions.iter().zip(weights) {
+        let snow = &region_sampler.regions[r].marine_snow;
+        density += snow.night_density.lerp(snow.density, daylight) * w;
+        let night = snow.night_colour.to_linear().to_vec4();
+        let day = snow.colour.to_linear().to_vec4();
+        colour += night.lerp(day, daylight) * w;
+    }
+
+    let Some(material) = materials.get_mut(&state.material) else {
+        return;
+    };
+    material.snow.colour = colour.truncate().extend(OPACITY);
+    material.snow.drift = state.drift;
+    material.snow.density = density;
+}
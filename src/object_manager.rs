@@ -1,346 +1,97 @@
 use crate::object_manager;
-use bevy::app::{App, Plugin, Startup};
-use bevy::asset::{AssetServer, Handle};
+use crate::placement::{PlacementDef, PlacementRules};
+use crate::stable_hash::StableHasher;
+use bevy::app::{App, Plugin, Startup, Update};
+use bevy::asset::io::Reader;
+use bevy::asset::{
+    Asset, AssetApp, AssetLoader, AssetServer, Assets, Handle, LoadContext, LoadState,
+};
 use bevy::gltf::GltfAssetLabel;
-use bevy::image::Image;
-use bevy::prelude::{Res, ResMut, Resource, Scene};
+use bevy::log::{info, warn};
+use bevy::prelude::{IntoScheduleConfigs, Res, ResMut, Resource, Scene};
+use bevy::reflect::TypePath;
 use glam::Vec2;
+use serde::Deserialize;
 use std::collections::HashMap;
-use std::ops::{Range, RangeInclusive};
+use std::ops::Range;
+use thiserror::Error;
 
+/// Loads the object catalogue from one or more `.objects.ron` manifests.
+/// The plugin registers the core manifest; asset packs can merge extra
+/// manifests on top with `app.add_object_manifest(..)`.
 #[derive(Default)]
 pub struct ObjectManagerPlugin;
 
 impl Plugin for ObjectManagerPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(ObjectManager::new(vec![
-            ObjectDefinition {
-                name: "acropora_2_anten_v".into(),
-                path: "models/objects/acropora_2_anten_v.glb".into(),
-                orientation_type: OrientationType::VerticalForward,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_3_anten".into(),
-                path: "models/objects/acropora_3_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_4_anten".into(),
-                path: "models/objects/acropora_4_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_abrolhosensis_anten".into(),
-                path: "models/objects/acropora_abrolhosensis_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_anten".into(),
-                path: "models/objects/acropora_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_cytherea_2_komang".into(),
-                path: "models/objects/acropora_cytherea_2_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_cytherea_anten".into(),
-                path: "models/objects/acropora_cytherea_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_cytherea_pink_2_komang".into(),
-                path: "models/objects/acropora_cytherea_pink_2_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_cytherea_pink_anten".into(),
-                path: "models/objects/acropora_cytherea_pink_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_natalensis_komang".into(),
-                path: "models/objects/acropora_natalensis_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "acropora_sukarnoi_komang".into(),
-                path: "models/objects/acropora_sukarnoi_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "big_brain_paula_q".into(),
-                path: "models/objects/big_brain_paula_q.glb".into(),
-                orientation_type: OrientationType::Quarter,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "big_sponge_paula".into(),
-                path: "models/objects/big_sponge_paula.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(2.0, 2.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "block_arch_paula".into(),
-                path: "models/objects/block_arch_paula.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(2.0, 2.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "concrete_dome_anten".into(),
-                path: "models/objects/concrete_dome_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "concrete_turtle_anten".into(),
-                path: "models/objects/concrete_turtle_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "concrete_turtle_komang".into(),
-                path: "models/objects/concrete_turtle_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "coral_table_komang".into(),
-                path: "models/objects/coral_table_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 1.0..1.0,
-            },
-            ObjectDefinition {
-                name: "diploastrea_heliopora_komang".into(),
-                path: "models/objects/diploastrea_heliopora_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "favia_komang".into(),
-                path: "models/objects/favia_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "leptoria_phrygia_2_anten".into(),
-                path: "models/objects/leptoria_phrygia_2_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "leptoria_phrygia_komang".into(),
-                path: "models/objects/leptoria_phrygia_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "les_komang".into(),
-                path: "models/objects/les_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "lobophytum_komang".into(),
-                path: "models/objects/lobophytum_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "mixed_coral_anten".into(),
-                path: "models/objects/mixed_coral_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "montipora_digitata_komang".into(),
-                path: "models/objects/montipora_digitata_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "pincushion_starfish_komang".into(),
-                path: "models/objects/pincushion_starfish_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "pocillopora_meandrina_komang".into(),
-                path: "models/objects/pocillopora_meandrina_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_2_anten".into(),
-                path: "models/objects/porites_lutea_2_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_3_anten".into(),
-                path: "models/objects/porites_lutea_3_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_4_komang".into(),
-                path: "models/objects/porites_lutea_4_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_5_anten".into(),
-                path: "models/objects/porites_lutea_5_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_6_anten".into(),
-                path: "models/objects/porites_lutea_6_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_7_anten".into(),
-                path: "models/objects/porites_lutea_7_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "porites_lutea_anten_q".into(),
-                path: "models/objects/porites_lutea_anten_q.glb".into(),
-                orientation_type: OrientationType::Quarter,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "small_yellow_coral_paula".into(),
-                path: "models/objects/small_yellow_coral_paula.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "sponge_4_anten".into(),
-                path: "models/objects/sponge_4_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "sponge_5_anten".into(),
-                path: "models/objects/sponge_5_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "sponge_komang".into(),
-                path: "models/objects/sponge_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "starfish_2_komang".into(),
-                path: "models/objects/starfish_2_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "starfish_komang".into(),
-                path: "models/objects/starfish_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "tendrils".into(),
-                path: "models/objects/tendrils.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.3, 0.3),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "tunnel_komang".into(),
-                path: "models/objects/tunnel_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "turbinaria_2_anten".into(),
-                path: "models/objects/turbinaria_2_anten.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.2, 0.2),
-                scale: 0.66..1.5,
-            },
-            ObjectDefinition {
-                name: "turbinaria_photos_komang".into(),
-                path: "models/objects/turbinaria_photos_komang.glb".into(),
-                orientation_type: OrientationType::HorizontalFree,
-                size: Vec2::new(0.5, 1.0),
-                scale: 0.66..1.5,
-            },
-        ]))
-        .add_systems(Startup, object_manager::asset_manager_init);
+        // packs may have been added already; core still merges first
+        app.world_mut().get_resource_or_init::<ObjectManifests>().core =
+            Some(CORE_MANIFEST.to_string());
+        app.init_asset::<ObjectManifest>()
+            .init_asset_loader::<ObjectManifestLoader>()
+            .init_resource::<ObjectManager>()
+            .add_systems(Startup, object_manager::asset_manager_init)
+            .add_systems(
+                Update,
+                merge_object_manifests.run_if(|manager: Res<ObjectManager>| !manager.catalogue_ready),
+            );
     }
 }
 
-#[derive(Clone, Debug)]
+pub trait ObjectCatalogueAppExt {
+    /// Register an extra `.objects.ron` manifest. Extra manifests merge
+    /// after the core one, whenever they were added, and among themselves
+    /// in the order they were added; a later definition with the same name
+    /// replaces the earlier one.
+    fn add_object_manifest(&mut self, path: impl Into<String>) -> &mut Self;
+}
+
+impl ObjectCatalogueAppExt for App {
+    fn add_object_manifest(&mut self, path: impl Into<String>) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ObjectManifests>()
+            .paths
+            .push(path.into());
+        self
+    }
+}
+
+/// The manifest `ObjectManagerPlugin` builds the catalogue on.
+const CORE_MANIFEST: &str = "objects/core.objects.ron";
+
+/// The manifests making up the catalogue.
+#[derive(Resource, Default)]
+pub struct ObjectManifests {
+    /// Merged first, so packs can replace its definitions.
+    pub core: Option<String>,
+    /// Extra manifests, in the order they merge after `core`.
+    pub paths: Vec<String>,
+    handles: Vec<Handle<ObjectManifest>>,
+}
+
+impl ObjectManifests {
+    /// Every manifest, in merge order.
+    fn in_merge_order(&self) -> impl Iterator<Item = &String> {
+        self.core.iter().chain(&self.paths)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
 pub enum OrientationType {
     HorizontalFree,
     VerticalForward,
     Quarter,
 }
 
+/// What part an object plays in reef restoration, if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum RestorationRole {
+    /// Something built for coral to grow on; fragments can be planted on it.
+    Structure,
+    /// A coral the turtle can break a fragment off to carry.
+    Fragment,
+}
+
 #[derive(Clone, Debug)]
 pub struct ObjectDefinition {
     pub name: String,
@@ -348,6 +99,11 @@ pub struct ObjectDefinition {
     pub orientation_type: OrientationType,
     pub size: Vec2,
     pub scale: Range<f32>,
+    pub placement: PlacementRules,
+    /// Living coral, which can bleach. See `reef_health`.
+    pub coral: bool,
+    pub restoration: Option<RestorationRole>,
+    pub credits: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -356,30 +112,59 @@ pub struct ObjectData {
     pub model_handle: Handle<Scene>,
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Default)]
 pub struct ObjectManager {
     pub objects: HashMap<String, ObjectData>,
-    pub floor_texture: Handle<Image>,
+    /// Set once every manifest has loaded and been merged into `objects`.
+    pub catalogue_ready: bool,
 }
 
 impl ObjectManager {
-    pub fn new(object_definitions: Vec<ObjectDefinition>) -> Self {
-        let mut objects_data = HashMap::new();
-        for def in object_definitions.iter() {
-            objects_data.insert(
-                def.name.clone(),
-                ObjectData {
-                    object_definition: def.clone(),
-                    model_handle: Default::default(),
-                },
-            );
-        }
-        ObjectManager {
-            objects: objects_data,
-            floor_texture: Handle::default(),
+    /// Add a definition, replacing any existing one with the same name.
+    pub fn insert(&mut self, def: ObjectDefinition) {
+        self.objects.insert(
+            def.name.clone(),
+            ObjectData {
+                object_definition: def,
+                model_handle: Default::default(),
+            },
+        );
+    }
+
+    /// Add every definition in `manifest`, replacing any already there
+    /// with the same name, so whichever merges last wins.
+    pub fn merge(&mut self, manifest: &ObjectManifest) {
+        for def in &manifest.objects {
+            self.insert(def.clone());
         }
     }
 
+    /// A hash of what scattering reads from each definition, the same from
+    /// one run to the next whatever order they were merged in.
+    pub fn fingerprint(&self) -> u64 {
+        let mut defs: Vec<&ObjectDefinition> =
+            self.objects.values().map(|o| &o.object_definition).collect();
+        defs.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut hasher = StableHasher::new();
+        hasher.u64(defs.len() as u64);
+        for def in defs {
+            let orientation = match def.orientation_type {
+                OrientationType::HorizontalFree => 0,
+                OrientationType::VerticalForward => 1,
+                OrientationType::Quarter => 2,
+            };
+            hasher
+                .str(&def.name)
+                .u32(orientation)
+                .f32(def.size.x)
+                .f32(def.size.y)
+                .f32(def.scale.start)
+                .f32(def.scale.end);
+            def.placement.hash_into(&mut hasher);
+        }
+        hasher.finish()
+    }
 
     /// Grab a reference to an object (with its handle if loaded)
     pub fn get(&self, name: &str) -> Option<&ObjectData> {
@@ -388,22 +173,64 @@ impl ObjectManager {
 }
 
 pub fn asset_manager_init(
+    asset_server: Res<AssetServer>,
+    mut manifests: ResMut<ObjectManifests>,
+) {
+    manifests.handles = manifests
+        .in_merge_order()
+        .map(|path| asset_server.load(path.clone()))
+        .collect();
+}
+
+/// Once every manifest is in, merge them (in order) into the catalogue
+/// and start loading the models.
+fn merge_object_manifests(
     asset_server: Res<AssetServer>,
     mut object_manager: ResMut<ObjectManager>,
+    manifests: Res<ObjectManifests>,
+    manifest_assets: Res<Assets<ObjectManifest>>,
 ) {
+    // wait until every manifest has either loaded or failed
+    if manifests.handles.is_empty()
+        || manifests.handles.iter().any(|h| {
+            matches!(
+                asset_server.load_state(h),
+                LoadState::NotLoaded | LoadState::Loading
+            )
+        })
+    {
+        return;
+    }
+
+    for (path, handle) in manifests.in_merge_order().zip(&manifests.handles) {
+        match manifest_assets.get(handle) {
+            Some(manifest) => object_manager.merge(manifest),
+            None => warn!("Skipping object manifest {path}, it failed to load"),
+        }
+    }
+
     for obj in object_manager.objects.values_mut() {
         let handle = asset_server
             .load(GltfAssetLabel::Scene(0).from_asset(obj.object_definition.path.clone()));
         obj.model_handle = handle;
     }
 
-    object_manager.floor_texture = asset_server.load("textures/temp_floor_2.png");
+    info!(
+        "Object catalogue: {} objects from {} manifests",
+        object_manager.objects.len(),
+        manifests.handles.len()
+    );
+    object_manager.catalogue_ready = true;
 }
 
 pub fn asset_manager_ready(
     object_manger: Res<ObjectManager>,
     asset_server: Res<AssetServer>,
 ) -> bool {
+    if !object_manger.catalogue_ready {
+        return false;
+    }
+
     for obj in object_manger.objects.values() {
         if !asset_server.is_loaded(&obj.model_handle) {
             return false;
@@ -412,3 +239,186 @@ pub fn asset_manager_ready(
 
     true
 }
+
+/// One `.objects.ron` file's worth of object definitions.
+#[derive(Asset, TypePath)]
+pub struct ObjectManifest {
+    pub objects: Vec<ObjectDefinition>,
+}
+
+#[derive(Default)]
+pub struct ObjectManifestLoader;
+
+#[derive(Debug, Error)]
+pub enum ObjectManifestLoadError {
+    #[error("could not read object manifest: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("object manifest is not valid RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("object \"{name}\": {reason}")]
+    InvalidObject { name: String, reason: String },
+}
+
+impl AssetLoader for ObjectManifestLoader {
+    type Asset = ObjectManifest;
+    type Settings = ();
+    type Error = ObjectManifestLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &Self::Settings,
+        _: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        parse_manifest(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["objects.ron"]
+    }
+}
+
+/// Parse and validate an `.objects.ron` manifest.
+fn parse_manifest(bytes: &[u8]) -> Result<ObjectManifest, ObjectManifestLoadError> {
+    let file: ObjectManifestFile = ron::de::from_bytes(bytes)?;
+
+    let mut objects = Vec::with_capacity(file.objects.len());
+    for def in file.objects {
+        let invalid = |reason: &str| ObjectManifestLoadError::InvalidObject {
+            name: def.name.clone(),
+            reason: reason.into(),
+        };
+        // so NaN and infinities fail too
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+        if !positive(def.size.0) || !positive(def.size.1) {
+            return Err(invalid("size must be a finite number greater than zero"));
+        }
+        if !positive(def.scale.0) || !positive(def.scale.1) || def.scale.0 > def.scale.1 {
+            return Err(invalid("scale must be a positive, finite (min, max) range"));
+        }
+        let placement = def.placement.build().map_err(|reason| invalid(&reason))?;
+        objects.push(ObjectDefinition {
+            name: def.name,
+            path: def.path,
+            orientation_type: def.orientation,
+            size: Vec2::new(def.size.0, def.size.1),
+            scale: def.scale.0..def.scale.1,
+            placement,
+            coral: def.coral,
+            restoration: def.restoration,
+            credits: def.credits,
+        });
+    }
+
+    Ok(ObjectManifest { objects })
+}
+
+#[derive(Deserialize)]
+struct ObjectManifestFile {
+    objects: Vec<ObjectDefinitionDef>,
+}
+
+#[derive(Deserialize)]
+struct ObjectDefinitionDef {
+    name: String,
+    path: String,
+    orientation: OrientationType,
+    size: (f32, f32),
+    scale: (f32, f32),
+    #[serde(default)]
+    placement: PlacementDef,
+    #[serde(default)]
+    coral: bool,
+    #[serde(default)]
+    restoration: Option<RestorationRole>,
+    #[serde(default)]
+    credits: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::asset::AssetPlugin;
+    use bevy::MinimalPlugins;
+
+    fn def(name: &str, path: &str) -> ObjectDefinition {
+        ObjectDefinition {
+            name: name.into(),
+            path: path.into(),
+            orientation_type: OrientationType::HorizontalFree,
+            size: Vec2::ONE,
+            scale: 1.0..1.0,
+            placement: PlacementRules::default(),
+            coral: false,
+            restoration: None,
+            credits: vec![],
+        }
+    }
+
+    #[test]
+    fn core_merges_before_packs_in_the_order_added() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .add_object_manifest("packs/first.objects.ron")
+            .add_plugins(ObjectManagerPlugin)
+            .add_object_manifest("packs/second.objects.ron");
+
+        let manifests = app.world().resource::<ObjectManifests>();
+        let order: Vec<&str> = manifests.in_merge_order().map(String::as_str).collect();
+        assert_eq!(
+            order,
+            [CORE_MANIFEST, "packs/first.objects.ron", "packs/second.objects.ron"]
+        );
+    }
+
+    #[test]
+    fn later_manifest_replaces_same_name() {
+        let core = ObjectManifest {
+            objects: vec![def("Brain Coral", "core/brain.glb"), def("Rock", "core/rock.glb")],
+        };
+        let pack = ObjectManifest {
+            objects: vec![def("Brain Coral", "pack/brain.glb"), def("Sponge", "pack/sponge.glb")],
+        };
+
+        let mut manager = ObjectManager::default();
+        manager.merge(&core);
+        manager.merge(&pack);
+
+        let path = |name| manager.get(name).unwrap().object_definition.path.as_str();
+        assert_eq!(manager.objects.len(), 3);
+        assert_eq!(path("Brain Coral"), "pack/brain.glb");
+        assert_eq!(path("Rock"), "core/rock.glb");
+        assert_eq!(path("Sponge"), "pack/sponge.glb");
+    }
+
+    #[test]
+    fn manifest_rejects_non_finite_sizes_and_scales() {
+        let manifest = |size: &str, scale: &str| {
+            format!(
+                r#"(objects: [(name: "Rock", path: "rock.glb", orientation: HorizontalFree,
+                    size: {size}, scale: {scale})])"#
+            )
+        };
+        assert!(parse_manifest(manifest("(1.0, 2.0)", "(0.5, 1.5)").as_bytes()).is_ok());
+        for (size, scale) in [
+            ("(NaN, 1.0)", "(0.5, 1.5)"),
+            ("(1.0, inf)", "(0.5, 1.5)"),
+            ("(0.0, 1.0)", "(0.5, 1.5)"),
+            ("(1.0, 1.0)", "(NaN, 1.5)"),
+            ("(1.0, 1.0)", "(0.5, NaN)"),
+            ("(1.0, 1.0)", "(0.5, inf)"),
+            ("(1.0, 1.0)", "(1.5, 0.5)"),
+        ] {
+            let text = manifest(size, scale);
+            assert!(
+                matches!(
+                    parse_manifest(text.as_bytes()),
+                    Err(ObjectManifestLoadError::InvalidObject { .. })
+                ),
+                "size {size}, scale {scale} loaded"
+            );
+        }
+    }
+}
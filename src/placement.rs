@@ -0,0 +1,164 @@
+use crate::stable_hash::StableHasher;
+use serde::Deserialize;
+use std::ops::Range;
+
+/// Where on the seabed an object is allowed to go. Set per object in its
+/// manifest, and optionally overridden per region in `.regions.ron`.
+#[derive(Clone, Debug)]
+pub struct PlacementRules {
+    /// Ground slope in degrees from horizontal (0 flat, 90 a sheer wall).
+    pub slope: Range<f32>,
+    /// World height of the ground.
+    pub height: Range<f32>,
+    /// No two of this object closer than this, on top of footprints not
+    /// overlapping.
+    pub spacing: f32,
+    /// 0 scatters evenly; towards 1 the object only shows up in patches.
+    pub clustering: f32,
+}
+
+impl Default for PlacementRules {
+    fn default() -> Self {
+        PlacementRules {
+            slope: 0.0..28.0,
+            height: f32::NEG_INFINITY..f32::INFINITY,
+            spacing: 0.0,
+            clustering: 0.0,
+        }
+    }
+}
+
+impl PlacementRules {
+    /// Whether ground with this slope (degrees) and height is acceptable.
+    /// Both ranges include their ends.
+    pub fn allows(&self, slope: f32, height: f32) -> bool {
+        (self.slope.start..=self.slope.end).contains(&slope)
+            && (self.height.start..=self.height.end).contains(&height)
+    }
+
+    pub(crate) fn hash_into(&self, hasher: &mut StableHasher) {
+        hasher
+            .f32(self.slope.start)
+            .f32(self.slope.end)
+            .f32(self.height.start)
+            .f32(self.height.end)
+            .f32(self.spacing)
+            .f32(self.clustering);
+    }
+}
+
+/// On-disk form of `PlacementRules`, shared by object manifests and region
+/// object entries. Anything left out keeps the default.
+#[derive(Deserialize)]
+pub struct PlacementDef {
+    #[serde(default = "default_slope")]
+    slope: (f32, f32),
+    #[serde(default = "default_height")]
+    height: (f32, f32),
+    #[serde(default)]
+    spacing: f32,
+    #[serde(default)]
+    clustering: f32,
+}
+
+impl Default for PlacementDef {
+    fn default() -> Self {
+        PlacementDef {
+            slope: default_slope(),
+            height: default_height(),
+            spacing: 0.0,
+            clustering: 0.0,
+        }
+    }
+}
+
+fn default_slope() -> (f32, f32) {
+    let slope = PlacementRules::default().slope;
+    (slope.start, slope.end)
+}
+fn default_height() -> (f32, f32) {
+    (f32::NEG_INFINITY, f32::INFINITY)
+}
+
+impl PlacementDef {
+    pub fn build(&self) -> Result<PlacementRules, String> {
+        // written as range checks so NaN fails them too
+        let (slope_min, slope_max) = self.slope;
+        let degrees = 0.0..=90.0;
+        if !degrees.contains(&slope_min) || !degrees.contains(&slope_max) || slope_min > slope_max {
+            return Err("placement slope must be a (min, max) range within 0..90 degrees".into());
+        }
+        // unbounded heights are fine, that's the default
+        let (height_min, height_max) = self.height;
+        if height_min.is_nan() || height_max.is_nan() || height_min > height_max {
+            return Err("placement height must be a (min, max) range".into());
+        }
+        if !(0.0..f32::INFINITY).contains(&self.spacing) {
+            return Err("placement spacing must be a finite number, not negative".into());
+        }
+        if !(0.0..=1.0).contains(&self.clustering) {
+            return Err("placement clustering must be between 0 and 1".into());
+        }
+        Ok(PlacementRules {
+            slope: slope_min..slope_max,
+            height: height_min..height_max,
+            spacing: self.spacing,
+            clustering: self.clustering,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(text: &str) -> Result<PlacementRules, String> {
+        ron::from_str::<PlacementDef>(text).unwrap().build()
+    }
+
+    #[test]
+    fn default_round_trips_through_the_def() {
+        for rules in [PlacementDef::default().build().unwrap(), build("()").unwrap()] {
+            let default = PlacementRules::default();
+            assert_eq!(rules.slope, default.slope);
+            assert_eq!(rules.height, default.height);
+            assert_eq!(rules.spacing, default.spacing);
+            assert_eq!(rules.clustering, default.clustering);
+        }
+    }
+
+    #[test]
+    fn build_rejects_bad_ranges_and_non_finite_numbers() {
+        for text in [
+            "(slope: (-1.0, 30.0))",
+            "(slope: (0.0, 91.0))",
+            "(slope: (40.0, 30.0))",
+            "(slope: (NaN, 30.0))",
+            "(slope: (0.0, NaN))",
+            "(height: (2.0, 1.0))",
+            "(height: (NaN, 1.0))",
+            "(height: (-inf, NaN))",
+            "(spacing: -0.5)",
+            "(spacing: NaN)",
+            "(spacing: inf)",
+            "(clustering: 1.5)",
+            "(clustering: NaN)",
+            "(clustering: inf)",
+        ] {
+            assert!(build(text).is_err(), "{text} built");
+        }
+        let rules = build("(slope: (10.0, 45.0), height: (-inf, -2.0), spacing: 1.5, clustering: 1.0)").unwrap();
+        assert_eq!(rules.height, f32::NEG_INFINITY..-2.0);
+    }
+
+    #[test]
+    fn allows_includes_both_ends() {
+        let rules = build("(slope: (10.0, 45.0), height: (-5.0, -2.0))").unwrap();
+        for (slope, height) in [(10.0, -5.0), (45.0, -2.0), (20.0, -3.0)] {
+            assert!(rules.allows(slope, height), "{slope}, {height}");
+        }
+        for (slope, height) in [(9.9, -3.0), (45.1, -3.0), (20.0, -5.1), (20.0, -1.9)] {
+            assert!(!rules.allows(slope, height), "{slope}, {height}");
+        }
+    }
+}
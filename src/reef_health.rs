@@ -0,0 +1,341 @@
+use crate::chunked_env::ChunkSettings;
+use crate::env_manager::{EnvManager, MainLight};
+use crate::object_manager::ObjectManager;
+use crate::region_sampler::RegionSampler;
+use crate::world_edits::ChunkObject;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+/// How far the water temperature swings either side of its average over a
+/// day, in °C.
+const DAILY_SWING: f32 = 0.5;
+/// Time of day the water is warmest: late afternoon, after the sun's been
+/// on it all day.
+const WARMEST_AT: f32 = 0.85;
+/// Bleaching shows in this many steps, each a shared material per model
+/// material rather than one per coral.
+const TINT_STEPS: f32 = 8.0;
+/// How bright a fully bleached coral's glow is, as a fraction of a white
+/// surface in the current sunlight.
+const BLEACHED_GLOW: f32 = 0.35;
+
+/// Corals bleach when their water runs warm and recover when it cools,
+/// going pale to match.
+///
+/// Call `.add_plugins(ReefHealthPlugin)` in your App.
+pub struct ReefHealthPlugin;
+
+impl Plugin for ReefHealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BleachingModel>()
+            .init_resource::<BleachTints>()
+            .add_systems(
+                Update,
+                (
+                    track_coral_health.run_if(resource_exists::<RegionSampler>),
+                    advance_coral_health,
+                    tint_bleached_corals,
+                    match_bleach_glow,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// How corals respond to water temperature. Plain numbers in and out, so
+/// it can be tried out without a world to run it in.
+#[derive(Resource, Clone, Debug)]
+pub struct BleachingModel {
+    /// °C above which corals start to bleach.
+    pub threshold: f32,
+    /// °C past `threshold` at which they bleach right through.
+    pub tolerance: f32,
+    /// In-game days to go from healthy to fully bleached, at worst.
+    pub bleach_days: f32,
+    /// In-game days to recover fully once the water cools.
+    pub recover_days: f32,
+}
+
+impl Default for BleachingModel {
+    fn default() -> Self {
+        BleachingModel {
+            threshold: 29.5,
+            tolerance: 2.5,
+            bleach_days: 1.0,
+            recover_days: 4.0,
+        }
+    }
+}
+
+impl BleachingModel {
+    /// The health a coral settles at in water of `temperature`: 1 healthy,
+    /// 0 bleached white.
+    pub fn settled_health(&self, temperature: f32) -> f32 {
+        1.0 - ((temperature - self.threshold) / self.tolerance).clamp(0.0, 1.0)
+    }
+
+    /// `health` after `days` in water of `temperature`. It heads for the
+    /// settled health, faster going down than coming back.
+    pub fn step(&self, health: f32, temperature: f32, days: f32) -> f32 {
+        let settled = self.settled_health(temperature);
+        if health > settled {
+            (health - days / self.bleach_days).max(settled)
+        } else {
+            (health + days / self.recover_days).min(settled)
+        }
+    }
+}
+
+/// Water temperature at `time_of_day` somewhere averaging `average` °C.
+pub fn water_temperature(average: f32, time_of_day: f32) -> f32 {
+    average + DAILY_SWING * ((time_of_day - WARMEST_AT) * TAU).cos()
+}
+
+/// How pale `health` looks, in whole tint steps (0 untouched).
+pub fn tint_step(health: f32) -> u8 {
+    ((1.0 - health.clamp(0.0, 1.0)) * TINT_STEPS).round() as u8
+}
+
+/// On every coral in the world.
+#[derive(Component, Debug)]
+pub struct CoralHealth {
+    /// 1 healthy, 0 bleached white.
+    pub health: f32,
+    /// Average water temperature where it grows, from the regions.
+    pub average_temperature: f32,
+    /// Tint step its materials show.
+    shown: u8,
+}
+
+impl CoralHealth {
+    pub fn new(health: f32, average_temperature: f32) -> Self {
+        CoralHealth {
+            health,
+            average_temperature,
+            shown: 0,
+        }
+    }
+}
+
+/// The material a bleached coral's mesh had before it was tinted.
+#[derive(Component)]
+struct UnbleachedMaterial(Handle<StandardMaterial>);
+
+/// Paler copies of coral materials, one per tint step in use.
+#[derive(Resource, Default)]
+struct BleachTints {
+    variants: HashMap<(AssetId<StandardMaterial>, u8), Handle<StandardMaterial>>,
+    /// Emissive strength of a fully bleached variant, following the sun.
+    glow: f32,
+}
+
+impl BleachTints {
+    fn variant(
+        &mut self,
+        original: &Handle<StandardMaterial>,
+        step: u8,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Option<Handle<StandardMaterial>> {
+        if step == 0 {
+            return Some(original.clone());
+        }
+        if let Some(handle) = self.variants.get(&(original.id(), step)) {
+            return Some(handle.clone());
+        }
+        let mut material = materials.get(original)?.clone();
+        paler(&mut material, step, self.glow);
+        let handle = materials.add(material);
+        self.variants.insert((original.id(), step), handle.clone());
+        Some(handle)
+    }
+}
+
+/// Bleach `material` to tint step `step`. Textured materials don't get any
+/// whiter from their base colour, so they also glow a little.
+fn paler(material: &mut StandardMaterial, step: u8, glow: f32) {
+    let bleached = step as f32 / TINT_STEPS;
+    material.base_color = material.base_color.mix(&Color::WHITE, bleached);
+    material.emissive = LinearRgba::WHITE * (glow * bleached);
+}
+
+/// Start tracking corals as they spawn, settled for the water they're in.
+fn track_coral_health(
+    mut commands: Commands,
+    objects: Query<(Entity, &Name, &ChunkObject, &Transform), Added<ChunkObject>>,
+    object_manager: Res<ObjectManager>,
+    region_sampler: Res<RegionSampler>,
+    settings: Res<ChunkSettings>,
+    env_manager: Res<EnvManager>,
+    model: Res<BleachingModel>,
+) {
+    for (entity, name, chunk_object, transform) in &objects {
+        let is_coral = object_manager
+            .get(name.as_str())
+            .is_some_and(|object| object.object_definition.coral);
+        if !is_coral {
+            continue;
+        }
+
+        // object transforms are relative to their chunk's centre
+        let half = settings.chunk_size * 0.5;
+        let chunk_center = chunk_object.coord.as_vec2() * settings.chunk_size + Vec2::splat(half);
+        let position = chunk_center + transform.translation.xz();
+        let (regions, weights) = region_sampler.sample_region(position);
+        let average_temperature = regions
+            .iter()
+            .zip(weights)
+            .map(|(&r, w)| region_sampler.regions[r].water_temperature * w)
+            .sum();
+
+        let temperature = env_manager.water_temperature(average_temperature);
+        commands.entity(entity).insert(CoralHealth::new(
+            model.settled_health(temperature),
+            average_temperature,
+        ));
+    }
+}
+
+fn advance_coral_health(
+    env_manager: Res<EnvManager>,
+    model: Res<BleachingModel>,
+    mut last_days: Local<Option<f64>>,
+    mut corals: Query<&mut CoralHealth>,
+) {
+    let days = env_manager.days();
+    let elapsed = last_days.map_or(0.0, |last| (days - last) as f32);
+    *last_days = Some(days);
+    if elapsed <= 0.0 {
+        return;
+    }
+
+    for mut coral in &mut corals {
+        let temperature = env_manager.water_temperature(coral.average_temperature);
+        coral.health = model.step(coral.health, temperature, elapsed);
+    }
+}
+
+/// Swap a coral's materials for paler ones when its tint step changes.
+fn tint_bleached_corals(
+    mut commands: Commands,
+    mut corals: Query<(Entity, &mut CoralHealth)>,
+    children: Query<&Children>,
+    mut meshes: Query<(
+        &mut MeshMaterial3d<StandardMaterial>,
+        Option<&UnbleachedMaterial>,
+    )>,
+    mut tints: ResMut<BleachTints>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (coral, mut health) in &mut corals {
+        let step = tint_step(health.health);
+        if step == health.shown {
+            continue;
+        }
+
+        let mut tinted = false;
+        for entity in children.iter_descendants(coral) {
+            let Ok((mut material, unbleached)) = meshes.get_mut(entity) else {
+                continue;
+            };
+            let original = match unbleached {
+                Some(unbleached) => unbleached.0.clone(),
+                None => {
+                    commands
+                        .entity(entity)
+                        .insert(UnbleachedMaterial(material.0.clone()));
+                    material.0.clone()
+                }
+            };
+            if let Some(handle) = tints.variant(&original, step, &mut materials) {
+                material.0 = handle;
+                tinted = true;
+            }
+        }
+        // the scene may not have spawned yet; try again next frame
+        if tinted {
+            health.shown = step;
+        }
+    }
+}
+
+/// Keep bleached corals' glow in step with the sunlight, so they stay pale
+/// rather than lit up at night.
+fn match_bleach_glow(
+    main_light: Query<&DirectionalLight, With<MainLight>>,
+    mut tints: ResMut<BleachTints>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Ok(light) = main_light.single() else {
+        return;
+    };
+    // a white surface under this much light is about illuminance / π bright
+    let glow = light.illuminance / PI * BLEACHED_GLOW;
+    if (glow - tints.glow).abs() <= 0.1 * tints.glow.max(1.0) {
+        return;
+    }
+
+    tints.glow = glow;
+    for (&(_, step), handle) in &tints.variants {
+        if let Some(material) = materials.get_mut(handle) {
+            material.emissive = LinearRgba::WHITE * (glow * step as f32 / TINT_STEPS);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settled_health_falls_across_the_tolerance() {
+        let model = BleachingModel::default();
+        assert_eq!(model.settled_health(model.threshold - 1.0), 1.0);
+        assert_eq!(model.settled_health(model.threshold), 1.0);
+        let halfway = model.settled_health(model.threshold + model.tolerance * 0.5);
+        assert!((halfway - 0.5).abs() < 1e-6);
+        assert_eq!(model.settled_health(model.threshold + model.tolerance), 0.0);
+        assert_eq!(model.settled_health(model.threshold + model.tolerance + 5.0), 0.0);
+    }
+
+    #[test]
+    fn step_bleaches_faster_than_it_recovers() {
+        let model = BleachingModel::default();
+        let hot = model.threshold + model.tolerance;
+        let cool = model.threshold - 1.0;
+        let lost = 1.0 - model.step(1.0, hot, 0.1);
+        let regained = model.step(0.0, cool, 0.1);
+        assert!(lost > regained);
+        assert!((lost - 0.1 / model.bleach_days).abs() < 1e-6);
+        assert!((regained - 0.1 / model.recover_days).abs() < 1e-6);
+    }
+
+    #[test]
+    fn step_never_overshoots_settled() {
+        let model = BleachingModel::default();
+        let warm = model.threshold + model.tolerance * 0.5;
+        let settled = model.settled_health(warm);
+        assert_eq!(model.step(1.0, warm, 100.0), settled);
+        assert_eq!(model.step(0.0, warm, 100.0), settled);
+        assert_eq!(model.step(settled, warm, 1.0), settled);
+    }
+
+    #[test]
+    fn water_is_warmest_at_warmest_at() {
+        let peak = water_temperature(28.0, WARMEST_AT);
+        assert!((peak - (28.0 + DAILY_SWING)).abs() < 1e-5);
+        for i in 0..100 {
+            assert!(water_temperature(28.0, i as f32 / 100.0) <= peak + 1e-5);
+        }
+        let coolest = water_temperature(28.0, (WARMEST_AT + 0.5).fract());
+        assert!((coolest - (28.0 - DAILY_SWING)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn tint_step_bounds() {
+        assert_eq!(tint_step(1.0), 0);
+        assert_eq!(tint_step(2.0), 0);
+        assert_eq!(tint_step(0.0), TINT_STEPS as u8);
+        assert_eq!(tint_step(-1.0), TINT_STEPS as u8);
+    }
+}
@@ -0,0 +1,1028 @@
+use crate::height_noise::{CaveField, HeightNoise};
+use crate::placement::PlacementDef;
+use crate::region_sampler::{
+    BehaviourKind, FaunaSelection, FloorLayer, FloorSet, LightingSetup, MarineSnow, ObjectSelection, Region,
+    RegionSampler, Species,
+};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use noise::Perlin;
+use serde::Deserialize;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Loads a `.regions.ron` world definition and keeps the `RegionSampler`
+/// resource in sync with it (including on hot reload).
+pub struct RegionsPlugin {
+    pub path: String,
+}
+
+impl Default for RegionsPlugin {
+    fn default() -> Self {
+        RegionsPlugin {
+            path: "worlds/reef.regions.ron".into(),
+        }
+    }
+}
+
+impl Plugin for RegionsPlugin {
+    fn build(&self, app: &mut App) {
+        let path = self.path.clone();
+        app.init_asset::<RegionsAsset>()
+            .init_asset_loader::<RegionsAssetLoader>()
+            .add_systems(
+                Startup,
+                move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                    commands.insert_resource(RegionsHandle(asset_server.load(path.clone())));
+                },
+            )
+            .add_systems(PreUpdate, apply_loaded_regions);
+    }
+}
+
+/// A fully built world definition, ready to become the `RegionSampler`.
+#[derive(Asset, TypePath)]
+pub struct RegionsAsset {
+    pub sampler: RegionSampler,
+}
+
+#[derive(Resource)]
+pub struct RegionsHandle(pub Handle<RegionsAsset>);
+
+#[derive(Default)]
+pub struct RegionsAssetLoader;
+
+#[derive(Debug, Error)]
+pub enum RegionsLoadError {
+    #[error("could not read regions file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("regions file is not valid RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("regions file has no regions")]
+    NoRegions,
+    #[error("`{field}` must be greater than zero (got {value})")]
+    NotPositive { field: &'static str, value: f32 },
+    #[error("`{field}` must be a finite number (got {value})")]
+    NotFinite { field: &'static str, value: f32 },
+    #[error("region \"{region}\": {reason}")]
+    InvalidRegion { region: String, reason: String },
+    #[error("region \"{region}\" uses unknown {kind} set \"{set}\"")]
+    UnknownSet {
+        region: String,
+        kind: &'static str,
+        set: String,
+    },
+}
+
+impl AssetLoader for RegionsAssetLoader {
+    type Asset = RegionsAsset;
+    type Settings = ();
+    type Error = RegionsLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _: &Self::Settings,
+        _: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(RegionsAsset {
+            sampler: parse_regions(&bytes)?,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["regions.ron"]
+    }
+}
+
+/// Parse and validate a `.regions.ron` file into a `RegionSampler`.
+pub fn parse_regions(bytes: &[u8]) -> Result<RegionSampler, RegionsLoadError> {
+    let file: RegionsFile = ron::de::from_bytes(bytes)?;
+    file.build()
+}
+
+/// Swap in a new `RegionSampler` whenever the regions file (re)loads.
+fn apply_loaded_regions(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<RegionsAsset>>,
+    handle: Option<Res<RegionsHandle>>,
+    assets: Res<Assets<RegionsAsset>>,
+) {
+    let Some(handle) = handle else {
+        return;
+    };
+    for event in events.read() {
+        match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }
+                if *id == handle.0.id() =>
+            {
+                if let Some(regions) = assets.get(*id) {
+                    info!("Loaded {} regions", regions.sampler.regions.len());
+                    commands.insert_resource(regions.sampler.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// ---- on-disk format ----
+
+#[derive(Deserialize)]
+struct RegionsFile {
+    cell_size: f32,
+    jitter: f32,
+    blend_dist: f32,
+    seed: u64,
+    #[serde(default)]
+    lighting_sets: HashMap<String, Vec<LightingSetupDef>>,
+    #[serde(default)]
+    object_sets: HashMap<String, Vec<ObjectEntryDef>>,
+    #[serde(default)]
+    height_sets: HashMap<String, HeightDef>,
+    #[serde(default)]
+    floor_sets: HashMap<String, FloorSetDef>,
+    #[serde(default)]
+    species: HashMap<String, SpeciesDef>,
+    regions: Vec<RegionDef>,
+}
+
+#[derive(Deserialize)]
+struct RegionDef {
+    name: String,
+    weight: u32,
+    height: HeightDef,
+    objects: Vec<ObjectEntryDef>,
+    lighting: LightingDef,
+    #[serde(default = "default_density")]
+    density: f32,
+    #[serde(default)]
+    caves: Option<CaveDef>,
+    #[serde(default)]
+    floor: Option<FloorDef>,
+    #[serde(default = "default_water_temperature")]
+    water_temperature: f32,
+    #[serde(default)]
+    marine_snow: Option<MarineSnowDef>,
+    #[serde(default)]
+    fauna: Vec<FaunaEntryDef>,
+}
+
+#[derive(Deserialize)]
+struct SpeciesDef {
+    model: String,
+    #[serde(default = "default_species_scale")]
+    scale: f32,
+    #[serde(default = "default_species_speed")]
+    speed: f32,
+    #[serde(default = "default_species_spacing")]
+    spacing: f32,
+    #[serde(default)]
+    turn: f32,
+    #[serde(default)]
+    behaviour: Option<BehaviourKind>,
+}
+
+fn default_species_scale() -> f32 {
+    1.0
+}
+
+fn default_species_speed() -> f32 {
+    1.0
+}
+
+fn default_species_spacing() -> f32 {
+    0.3
+}
+
+#[derive(Deserialize)]
+struct FaunaEntryDef {
+    species: String,
+    weight: u32,
+    #[serde(default = "default_fauna_depth")]
+    depth: (f32, f32),
+    #[serde(default = "default_fauna_active")]
+    active: (f32, f32),
+    group_size: (u32, u32),
+}
+
+fn default_fauna_depth() -> (f32, f32) {
+    (0.0, f32::INFINITY)
+}
+
+fn default_fauna_active() -> (f32, f32) {
+    (0.0, 1.0)
+}
+
+/// Anything left out keeps `MarineSnow::default()`.
+#[derive(Deserialize)]
+#[serde(default)]
+struct MarineSnowDef {
+    density: f32,
+    night_density: f32,
+    colour: (f32, f32, f32),
+    night_colour: (f32, f32, f32),
+}
+
+impl Default for MarineSnowDef {
+    fn default() -> Self {
+        let snow = MarineSnow::default();
+        let rgb = |colour: Color| {
+            let c = colour.to_srgba();
+            (c.red, c.green, c.blue)
+        };
+        MarineSnowDef {
+            density: snow.density,
+            night_density: snow.night_density,
+            colour: rgb(snow.colour),
+            night_colour: rgb(snow.night_colour),
+        }
+    }
+}
+
+impl MarineSnowDef {
+    fn build(&self) -> MarineSnow {
+        MarineSnow {
+            density: self.density,
+            night_density: self.night_density,
+            colour: Color::srgb(self.colour.0, self.colour.1, self.colour.2),
+            night_colour: Color::srgb(self.night_colour.0, self.night_colour.1, self.night_colour.2),
+        }
+    }
+}
+
+/// Turns a region into density-field terrain, see `CaveField`.
+#[derive(Deserialize)]
+struct CaveDef {
+    scale: f64,
+    strength: f32,
+    #[serde(default = "default_cave_octaves")]
+    octaves: u32,
+    #[serde(default)]
+    seed: u32,
+}
+
+fn default_cave_octaves() -> u32 {
+    3
+}
+
+fn default_water_temperature() -> f32 {
+    28.0
+}
+
+fn default_density() -> f32 {
+    0.25
+}
+
+/// One node of a region's height function, see `HeightNoise`.
+#[derive(Deserialize)]
+enum HeightDef {
+    Constant(f64),
+    Perlin {
+        scale: f64,
+        #[serde(default = "default_one")]
+        height: f64,
+        #[serde(default)]
+        seed: u32,
+    },
+    Fbm {
+        scale: f64,
+        #[serde(default = "default_one")]
+        height: f64,
+        #[serde(default)]
+        seed: u32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default = "default_lacunarity")]
+        lacunarity: f64,
+        #[serde(default = "default_persistence")]
+        persistence: f64,
+    },
+    Ridged {
+        scale: f64,
+        #[serde(default = "default_one")]
+        height: f64,
+        #[serde(default)]
+        seed: u32,
+        #[serde(default = "default_octaves")]
+        octaves: u32,
+        #[serde(default = "default_lacunarity")]
+        lacunarity: f64,
+        #[serde(default = "default_persistence")]
+        persistence: f64,
+    },
+    Warp {
+        input: Box<HeightDef>,
+        scale: f64,
+        strength: f64,
+        #[serde(default)]
+        seed: u32,
+    },
+    Terrace {
+        input: Box<HeightDef>,
+        steps: f64,
+        smooth_width: f32,
+        #[serde(default = "default_one")]
+        height: f64,
+    },
+    Curve {
+        input: Box<HeightDef>,
+        points: Vec<(f64, f64)>,
+    },
+    Remap {
+        input: Box<HeightDef>,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    Clamp {
+        input: Box<HeightDef>,
+        min: f64,
+        max: f64,
+    },
+    Sum(Vec<HeightDef>),
+    Product(Vec<HeightDef>),
+    Min(Vec<HeightDef>),
+    Max(Vec<HeightDef>),
+    Set(String),
+}
+
+fn default_one() -> f64 {
+    1.0
+}
+
+fn default_octaves() -> u32 {
+    4
+}
+
+fn default_lacunarity() -> f64 {
+    2.0
+}
+
+fn default_persistence() -> f64 {
+    0.5
+}
+
+#[derive(Deserialize)]
+enum ObjectEntryDef {
+    Set(String),
+    Object {
+        name: String,
+        weight: u32,
+        #[serde(default)]
+        placement: Option<PlacementDef>,
+    },
+}
+
+#[derive(Deserialize)]
+enum LightingDef {
+    Set(String),
+    Inline(Vec<LightingSetupDef>),
+}
+
+#[derive(Deserialize)]
+enum FloorDef {
+    Set(String),
+    Inline(FloorSetDef),
+}
+
+#[derive(Deserialize)]
+struct FloorSetDef {
+    flat: FloorLayerDef,
+    steep: FloorLayerDef,
+}
+
+#[derive(Deserialize)]
+struct FloorLayerDef {
+    texture: String,
+    #[serde(default = "default_tint")]
+    tint: (f32, f32, f32),
+    #[serde(default = "default_floor_scale")]
+    scale: f32,
+}
+
+fn default_tint() -> (f32, f32, f32) {
+    (1.0, 1.0, 1.0)
+}
+
+fn default_floor_scale() -> f32 {
+    2.0
+}
+
+#[derive(Clone, Deserialize)]
+struct LightingSetupDef {
+    name: String,
+    time: f32,
+    primary_color: (f32, f32, f32),
+    primary_illuminance: f32,
+    secondary_color: (f32, f32, f32),
+    secondary_illuminance: f32,
+    fog_colour: (f32, f32, f32),
+    clear_colour: (f32, f32, f32),
+}
+
+impl RegionsFile {
+    fn build(self) -> Result<RegionSampler, RegionsLoadError> {
+        for (field, value) in [
+            ("cell_size", self.cell_size),
+            ("jitter", self.jitter),
+            ("blend_dist", self.blend_dist),
+        ] {
+            if !value.is_finite() {
+                return Err(RegionsLoadError::NotFinite { field, value });
+            }
+        }
+        for (field, value) in [
+            ("cell_size", self.cell_size),
+            ("blend_dist", self.blend_dist),
+        ] {
+            if value <= 0.0 {
+                return Err(RegionsLoadError::NotPositive { field, value });
+            }
+        }
+        if self.regions.is_empty() {
+            return Err(RegionsLoadError::NoRegions);
+        }
+
+        let mut regions = Vec::with_capacity(self.regions.len());
+        for def in &self.regions {
+            regions.push(self.build_region(def)?);
+        }
+
+        Ok(RegionSampler::new(
+            regions,
+            self.cell_size,
+            self.jitter,
+            self.blend_dist,
+            self.seed,
+        ))
+    }
+
+    fn build_region(&self, def: &RegionDef) -> Result<Region, RegionsLoadError> {
+        let invalid = |reason: String| RegionsLoadError::InvalidRegion {
+            region: def.name.clone(),
+            reason,
+        };
+
+        if def.weight == 0 {
+            return Err(invalid("weight must be greater than zero".into()));
+        }
+        if !def.density.is_finite() {
+            return Err(invalid(format!("density must be a finite number (got {})", def.density)));
+        }
+        if def.density < 0.0 {
+            return Err(invalid("density can't be negative".into()));
+        }
+
+        let mut objects = Vec::new();
+        self.collect_objects(&def.name, &def.objects, &mut objects, 0)?;
+        if objects.iter().all(|o| o.selection_weight == 0) {
+            return Err(invalid("needs at least one object with a non-zero weight".into()));
+        }
+
+        let lighting = match &def.lighting {
+            LightingDef::Set(set) => {
+                self.lighting_sets
+                    .get(set)
+                    .ok_or_else(|| RegionsLoadError::UnknownSet {
+                        region: def.name.clone(),
+                        kind: "lighting",
+                        set: set.clone(),
+                    })?
+            }
+            LightingDef::Inline(setups) => setups,
+        };
+        if lighting.len() < 2 {
+            return Err(invalid("needs at least two lighting keyframes".into()));
+        }
+        if lighting.windows(2).any(|w| w[0].time > w[1].time) {
+            return Err(invalid("lighting keyframe times must be in ascending order".into()));
+        }
+
+        let mut region = Region::new(
+            def.name.clone(),
+            def.weight,
+            self.build_height(&def.name, &def.height, 0)?,
+            objects,
+            lighting.iter().map(LightingSetupDef::build).collect(),
+        );
+        region.density = def.density;
+        if !(0.0..=40.0).contains(&def.water_temperature) {
+            return Err(invalid("water temperature must be between 0 and 40 °C".into()));
+        }
+        region.water_temperature = def.water_temperature;
+        if let Some(snow) = &def.marine_snow {
+            let snow = snow.build();
+            if !(0.0..=1.0).contains(&snow.density) || !(0.0..=1.0).contains(&snow.night_density) {
+                return Err(invalid("marine snow densities must be between 0 and 1".into()));
+            }
+            region.marine_snow = snow;
+        }
+        for entry in &def.fauna {
+            let invalid_entry =
+                |reason: &str| invalid(format!("fauna \"{}\": {reason}", entry.species));
+            let species = self
+                .species
+                .get(&entry.species)
+                .ok_or_else(|| invalid_entry("unknown species"))?;
+            // so NaN and infinities fail too
+            let positive = |value: f32| value.is_finite() && value > 0.0;
+            if !positive(species.scale) || !positive(species.speed) || !positive(species.spacing) {
+                return Err(invalid_entry("species needs a positive, finite scale, speed and spacing"));
+            }
+            if !species.turn.is_finite() {
+                return Err(invalid_entry("species turn must be a finite number"));
+            }
+            // no deeper limit is fine, that's the default
+            let (depth_min, depth_max) = entry.depth;
+            if !(0.0..f32::INFINITY).contains(&depth_min)
+                || depth_max.is_nan()
+                || depth_min > depth_max
+            {
+                return Err(invalid_entry("depth must be a (min, max) range below the surface"));
+            }
+            let (start, end) = entry.active;
+            if !(0.0..=1.0).contains(&start) || !(0.0..=1.0).contains(&end) {
+                return Err(invalid_entry("active times of day must be between 0 and 1"));
+            }
+            let (size_min, size_max) = entry.group_size;
+            if size_min == 0 || size_min > size_max {
+                return Err(invalid_entry("group size must be a (min, max) range of at least one"));
+            }
+            region.fauna.push(FaunaSelection {
+                species: Species {
+                    name: entry.species.clone(),
+                    model: species.model.clone(),
+                    scale: species.scale,
+                    speed: species.speed,
+                    spacing: species.spacing,
+                    turn: species.turn.to_radians(),
+                    behaviour: species.behaviour,
+                },
+                weight: entry.weight,
+                depth: depth_min..depth_max,
+                active: start..end,
+                group_size: size_min..=size_max,
+            });
+        }
+        if let Some(caves) = &def.caves {
+            if caves.scale <= 0.0 || caves.strength <= 0.0 {
+                return Err(invalid("caves need a positive scale and strength".into()));
+            }
+            if !(1..=8).contains(&caves.octaves) {
+                return Err(invalid("cave octaves must be between 1 and 8".into()));
+            }
+            region.caves = Some(CaveField {
+                perlin: Perlin::new(caves.seed),
+                scale: caves.scale,
+                strength: caves.strength,
+                octaves: caves.octaves,
+            });
+        }
+        if let Some(floor) = &def.floor {
+            let floor = match floor {
+                FloorDef::Set(set) => {
+                    self.floor_sets
+                        .get(set)
+                        .ok_or_else(|| RegionsLoadError::UnknownSet {
+                            region: def.name.clone(),
+                            kind: "floor",
+                            set: set.clone(),
+                        })?
+                }
+                FloorDef::Inline(floor) => floor,
+            };
+            if floor.flat.scale <= 0.0 || floor.steep.scale <= 0.0 {
+                return Err(invalid("floor layer scale must be greater than zero".into()));
+            }
+            region.floor = floor.build();
+        }
+        Ok(region)
+    }
+
+    fn collect_objects(
+        &self,
+        region: &str,
+        entries: &[ObjectEntryDef],
+        out: &mut Vec<ObjectSelection>,
+        depth: usize,
+    ) -> Result<(), RegionsLoadError> {
+        if depth > 8 {
+            return Err(RegionsLoadError::InvalidRegion {
+                region: region.into(),
+                reason: "object sets nest too deeply (is one including itself?)".into(),
+            });
+        }
+        for entry in entries {
+            match entry {
+                ObjectEntryDef::Set(set) => {
+                    let set_entries =
+                        self.object_sets
+                            .get(set)
+                            .ok_or_else(|| RegionsLoadError::UnknownSet {
+                                region: region.into(),
+                                kind: "object",
+                                set: set.clone(),
+                            })?;
+                    self.collect_objects(region, set_entries, out, depth + 1)?;
+                }
+                ObjectEntryDef::Object {
+                    name,
+                    weight,
+                    placement,
+                } => out.push(ObjectSelection {
+                    name: name.clone(),
+                    selection_weight: *weight,
+                    placement: placement
+                        .as_ref()
+                        .map(|p| p.build())
+                        .transpose()
+                        .map_err(|reason| RegionsLoadError::InvalidRegion {
+                            region: region.into(),
+                            reason: format!("object \"{name}\": {reason}"),
+                        })?,
+                }),
+            }
+        }
+        Ok(())
+    }
+
+    fn build_height(
+        &self,
+        region: &str,
+        def: &HeightDef,
+        depth: usize,
+    ) -> Result<HeightNoise, RegionsLoadError> {
+        let invalid = |reason: &str| RegionsLoadError::InvalidRegion {
+            region: region.into(),
+            reason: format!("height: {reason}"),
+        };
+        if depth > 32 {
+            return Err(invalid("nodes nest too deeply (is a set including itself?)"));
+        }
+        let build = |input: &HeightDef| self.build_height(region, input, depth + 1).map(Box::new);
+        let build_all = |inputs: &[HeightDef]| {
+            if inputs.is_empty() {
+                return Err(invalid("node needs at least one input"));
+            }
+            inputs
+                .iter()
+                .map(|input| self.build_height(region, input, depth + 1))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let check_octaves = |octaves: u32, lacunarity: f64| {
+            if !(1..=16).contains(&octaves) {
+                return Err(invalid("octaves must be between 1 and 16"));
+            }
+            if lacunarity <= 0.0 {
+                return Err(invalid("lacunarity must be greater than zero"));
+            }
+            Ok(())
+        };
+
+        Ok(match def {
+            HeightDef::Constant(value) => HeightNoise::Constant(*value),
+            HeightDef::Perlin {
+                scale,
+                height,
+                seed,
+            } => HeightNoise::Perlin {
+                perlin: Perlin::new(*seed),
+                scale: *scale,
+                height: *height,
+            },
+            HeightDef::Fbm {
+                scale,
+                height,
+                seed,
+                octaves,
+                lacunarity,
+                persistence,
+            } => {
+                check_octaves(*octaves, *lacunarity)?;
+                HeightNoise::Fbm {
+                    perlin: Perlin::new(*seed),
+                    scale: *scale,
+                    height: *height,
+                    octaves: *octaves,
+                    lacunarity: *lacunarity,
+                    persistence: *persistence,
+                }
+            }
+            HeightDef::Ridged {
+                scale,
+                height,
+                seed,
+                octaves,
+                lacunarity,
+                persistence,
+            } => {
+                check_octaves(*octaves, *lacunarity)?;
+                HeightNoise::Ridged {
+                    perlin: Perlin::new(*seed),
+                    scale: *scale,
+                    height: *height,
+                    octaves: *octaves,
+                    lacunarity: *lacunarity,
+                    persistence: *persistence,
+                }
+            }
+            HeightDef::Warp {
+                input,
+                scale,
+                strength,
+                seed,
+            } => HeightNoise::Warp {
+                input: build(input)?,
+                perlin: Perlin::new(*seed),
+                scale: *scale,
+                strength: *strength,
+            },
+            HeightDef::Terrace {
+                input,
+                steps,
+                smooth_width,
+                height,
+            } => {
+                if !steps.is_finite() || *steps <= 0.0 {
+                    return Err(invalid("terrace steps must be a finite number greater than zero"));
+                }
+                if !(0.0..=1.0).contains(smooth_width) {
+                    return Err(invalid("terrace smooth_width must be between 0 and 1"));
+                }
+                if !height.is_finite() {
+                    return Err(invalid("terrace height must be a finite number"));
+                }
+                HeightNoise::Terrace {
+                    input: build(input)?,
+                    steps: *steps,
+                    smooth_width: *smooth_width,
+                    height: *height,
+                }
+            }
+            HeightDef::Curve { input, points } => {
+                if points.len() < 2 {
+                    return Err(invalid("curve needs at least two points"));
+                }
+                if points.windows(2).any(|w| w[0].0 > w[1].0) {
+                    return Err(invalid("curve points must be in ascending x order"));
+                }
+                HeightNoise::Curve {
+                    input: build(input)?,
+                    points: points.clone(),
+                }
+            }
+            HeightDef::Remap { input, from, to } => {
+                if from.0 == from.1 {
+                    return Err(invalid("remap `from` range is empty"));
+                }
+                HeightNoise::Remap {
+                    input: build(input)?,
+                    from: *from,
+                    to: *to,
+                }
+            }
+            HeightDef::Clamp { input, min, max } => {
+                if min > max {
+                    return Err(invalid("clamp `min` is above `max`"));
+                }
+                HeightNoise::Clamp {
+                    input: build(input)?,
+                    min: *min,
+                    max: *max,
+                }
+            }
+            HeightDef::Sum(inputs) => HeightNoise::Sum(build_all(inputs)?),
+            HeightDef::Product(inputs) => HeightNoise::Product(build_all(inputs)?),
+            HeightDef::Min(inputs) => HeightNoise::Min(build_all(inputs)?),
+            HeightDef::Max(inputs) => HeightNoise::Max(build_all(inputs)?),
+            HeightDef::Set(set) => {
+                let set_def =
+                    self.height_sets
+                        .get(set)
+                        .ok_or_else(|| RegionsLoadError::UnknownSet {
+                            region: region.into(),
+                            kind: "height",
+                            set: set.clone(),
+                        })?;
+                return self.build_height(region, set_def, depth + 1);
+            }
+        })
+    }
+}
+
+impl FloorSetDef {
+    fn build(&self) -> FloorSet {
+        let layer = |def: &FloorLayerDef| FloorLayer {
+            texture: def.texture.clone(),
+            tint: Color::srgb(def.tint.0, def.tint.1, def.tint.2),
+            scale: def.scale,
+        };
+        FloorSet {
+            flat: layer(&self.flat),
+            steep: layer(&self.steep),
+        }
+    }
+}
+
+impl LightingSetupDef {
+    fn build(&self) -> LightingSetup {
+        let srgb = |(r, g, b): (f32, f32, f32)| Color::srgb(r, g, b);
+        LightingSetup {
+            name: self.name.clone(),
+            primary_color: srgb(self.primary_color),
+            primary_illuminance: self.primary_illuminance,
+            secondary_color: srgb(self.secondary_color),
+            secondary_illuminance: self.secondary_illuminance,
+            time: self.time,
+            fog_colour: srgb(self.fog_colour),
+            clear_colour: srgb(self.clear_colour),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIGHTING: &str = r#"Inline([
+        (name: "Dawn", time: 0.0, primary_color: (1, 1, 1), primary_illuminance: 1000,
+         secondary_color: (1, 1, 1), secondary_illuminance: 0,
+         fog_colour: (0, 0, 0), clear_colour: (0, 0, 0)),
+        (name: "Dusk", time: 1.0, primary_color: (1, 1, 1), primary_illuminance: 1000,
+         secondary_color: (1, 1, 1), secondary_illuminance: 0,
+         fog_colour: (0, 0, 0), clear_colour: (0, 0, 0)),
+    ])"#;
+
+    /// A regions file with one region. `header` adds fields to the file
+    /// (sets, species) and `region` to the region.
+    fn regions_file(header: &str, region: &str) -> String {
+        format!(
+            r#"(
+                cell_size: 64.0, jitter: 0.5, blend_dist: 8.0, seed: 1,
+                {header}
+                regions: [(
+                    name: "Reef", weight: 1, height: Constant(0.0),
+                    objects: [Object(name: "Rock", weight: 1)],
+                    lighting: {LIGHTING},
+                    {region}
+                )],
+            )"#
+        )
+    }
+
+    fn parse(text: &str) -> Result<RegionSampler, RegionsLoadError> {
+        parse_regions(text.as_bytes())
+    }
+
+    fn error(text: &str) -> RegionsLoadError {
+        match parse(text) {
+            Ok(_) => panic!("parsed, but shouldn't have"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn shipped_regions_parse() {
+        let sampler = parse_regions(include_bytes!("../assets/worlds/reef.regions.ron"))
+            .unwrap_or_else(|err| panic!("reef.regions.ron: {err}"));
+        assert!(!sampler.regions.is_empty());
+    }
+
+    #[test]
+    fn minimal_file_parses() {
+        let sampler = parse(&regions_file("", "")).unwrap();
+        assert_eq!(sampler.regions[0].name, "Reef");
+    }
+
+    #[test]
+    fn sets_in_the_header_resolve() {
+        let header = r#"
+            height_sets: {"Hills": Constant(2.0)},
+            object_sets: {"Rocks": [Object(name: "Boulder", weight: 2)]},
+        "#;
+        let text = regions_file(header, "")
+            .replace("Constant(0.0)", r#"Set("Hills")"#)
+            .replace(r#"weight: 1)]"#, r#"weight: 1), Set("Rocks")]"#);
+        let sampler = parse(&text).unwrap();
+        assert_eq!(sampler.sample_surface_height(Vec2::ZERO), 2.0);
+        let objects: Vec<_> = sampler.regions[0]
+            .objects
+            .iter()
+            .map(|o| (o.name.as_str(), o.selection_weight))
+            .collect();
+        assert_eq!(objects, [("Rock", 1), ("Boulder", 2)]);
+    }
+
+    #[test]
+    fn rejects_no_regions() {
+        let text = "(cell_size: 64.0, jitter: 0.5, blend_dist: 8.0, seed: 1, regions: [])";
+        assert!(matches!(parse(text), Err(RegionsLoadError::NoRegions)));
+    }
+
+    #[test]
+    fn rejects_non_positive_sizes() {
+        let text = regions_file("", "").replace("cell_size: 64.0", "cell_size: 0.0");
+        assert!(matches!(
+            parse(&text),
+            Err(RegionsLoadError::NotPositive { field: "cell_size", .. })
+        ));
+        let text = regions_file("", "").replace("blend_dist: 8.0", "blend_dist: -1.0");
+        assert!(matches!(
+            parse(&text),
+            Err(RegionsLoadError::NotPositive { field: "blend_dist", .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_non_finite_numbers() {
+        for (from, to, field) in [
+            ("cell_size: 64.0", "cell_size: NaN", "cell_size"),
+            ("jitter: 0.5", "jitter: NaN", "jitter"),
+            ("blend_dist: 8.0", "blend_dist: inf", "blend_dist"),
+        ] {
+            let text = regions_file("", "").replace(from, to);
+            match error(&text) {
+                RegionsLoadError::NotFinite { field: got, .. } => assert_eq!(got, field),
+                err => panic!("{to}: expected NotFinite, got {err}"),
+            }
+        }
+        for density in ["NaN", "inf"] {
+            let err = error(&regions_file("", &format!("density: {density},")));
+            assert!(matches!(err, RegionsLoadError::InvalidRegion { .. }), "{density}: got {err}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_region() {
+        let text = regions_file("", "").replace("weight: 1, height", "weight: 0, height");
+        let err = error(&text);
+        assert!(matches!(err, RegionsLoadError::InvalidRegion { ref region, .. } if region == "Reef"));
+        assert_eq!(err.to_string(), "region \"Reef\": weight must be greater than zero");
+    }
+
+    #[test]
+    fn rejects_unknown_sets() {
+        let text = regions_file("", "floor: Some(Set(\"Sand\")),");
+        let err = error(&text);
+        assert!(matches!(err, RegionsLoadError::UnknownSet { kind: "floor", .. }));
+        assert_eq!(err.to_string(), "region \"Reef\" uses unknown floor set \"Sand\"");
+
+        let text = regions_file("", "").replace("Constant(0.0)", "Set(\"Hills\")");
+        assert!(matches!(
+            parse(&text),
+            Err(RegionsLoadError::UnknownSet { kind: "height", .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_terraces() {
+        for terrace in [
+            "steps: 0.0, smooth_width: 0.2",
+            "steps: NaN, smooth_width: 0.2",
+            "steps: 4.0, smooth_width: -0.1",
+            "steps: 4.0, smooth_width: 1.5",
+            "steps: 4.0, smooth_width: NaN",
+            "steps: 4.0, smooth_width: 0.2, height: inf",
+        ] {
+            let height = format!("Terrace(input: Constant(0.0), {terrace})");
+            let text = regions_file("", "").replace("Constant(0.0)", &height);
+            assert!(
+                matches!(parse(&text), Err(RegionsLoadError::InvalidRegion { .. })),
+                "{terrace} parsed"
+            );
+        }
+        // a hard terrace is fine
+        let height = "Terrace(input: Constant(0.0), steps: 4.0, smooth_width: 0.0)";
+        parse(&regions_file("", "").replace("Constant(0.0)", height)).unwrap();
+    }
+
+    #[test]
+    fn rejects_non_finite_species_and_depths() {
+        let fauna = r#"fauna: [(species: "Fish", weight: 1, group_size: (1, 2))],"#;
+        let with_species = |species: &str| {
+            regions_file(&format!(r#"species: {{"Fish": (model: "fish.glb", {species})}},"#), fauna)
+        };
+        parse(&with_species("")).unwrap();
+        for species in ["scale: NaN", "speed: inf", "spacing: NaN", "spacing: 0.0", "turn: NaN"] {
+            assert!(
+                matches!(parse(&with_species(species)), Err(RegionsLoadError::InvalidRegion { .. })),
+                "{species} parsed"
+            );
+        }
+        for depth in ["(NaN, 4.0)", "(1.0, NaN)", "(inf, inf)"] {
+            let text = with_species("").replace("group_size", &format!("depth: {depth}, group_size"));
+            assert!(
+                matches!(parse(&text), Err(RegionsLoadError::InvalidRegion { .. })),
+                "depth {depth} parsed"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_ron() {
+        assert!(matches!(parse("(cell_size: 64.0,"), Err(RegionsLoadError::Ron(_))));
+    }
+}
@@ -1,8 +1,13 @@
-use crate::height_noise::HeightNoise;
+use crate::height_noise::{CaveField, HeightNoise};
+use crate::placement::PlacementRules;
+use crate::stable_hash::StableHasher;
 use bevy::color::Color;
 use bevy::prelude::Resource;
-use glam::{Quat, Vec2, Vec3};
-use rand::Rng;
+use glam::{IVec2, Quat, UVec2, Vec2, Vec3};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Deserialize;
+use std::ops::{Range, RangeInclusive};
 
 /// Your region definition; add fields here as you go.
 #[derive(Clone, Debug)]
@@ -12,8 +17,17 @@ pub struct Region {
     pub height_sampler: HeightNoise,
     pub objects: Vec<ObjectSelection>,
     pub lighting_setups: Vec<LightingSetup>,
-    objects_prefix: Vec<u32>,
-    objects_total_weight: u32,
+    /// Scatter candidates per square unit of seabed; higher means denser reef.
+    pub density: f32,
+    /// Carves overhangs and caves into this region; `None` is a plain heightfield.
+    pub caves: Option<CaveField>,
+    pub floor: FloorSet,
+    /// Average water temperature in °C; corals start bleaching when it runs
+    /// warm. See `reef_health`.
+    pub water_temperature: f32,
+    pub marine_snow: MarineSnow,
+    /// Animals that can turn up here. See `fauna`.
+    pub fauna: Vec<FaunaSelection>,
 }
 
 #[derive(Clone, Debug)]
@@ -28,6 +42,106 @@ pub struct LightingSetup {
     pub clear_colour:Color,
 }
 
+/// How a region's seabed is painted: one layer for flat ground, one for
+/// slopes and cliff faces. See `floor_material`.
+#[derive(Clone, Debug)]
+pub struct FloorSet {
+    pub flat: FloorLayer,
+    pub steep: FloorLayer,
+}
+
+#[derive(Clone, Debug)]
+pub struct FloorLayer {
+    pub texture: String,
+    pub tint: Color,
+    /// World units per texture repeat.
+    pub scale: f32,
+}
+
+impl Default for FloorSet {
+    fn default() -> Self {
+        let layer = |tint| FloorLayer {
+            texture: "textures/temp_floor_2.png".into(),
+            tint,
+            scale: 2.0,
+        };
+        FloorSet {
+            flat: layer(Color::WHITE),
+            steep: layer(Color::srgb(0.6, 0.6, 0.6)),
+        }
+    }
+}
+
+/// A kind of animal, from the regions file's `species` table.
+#[derive(Clone, Debug)]
+pub struct Species {
+    pub name: String,
+    /// glTF the animal's scene comes from.
+    pub model: String,
+    pub scale: f32,
+    /// Cruising speed, units a second.
+    pub speed: f32,
+    /// How close school mates swim to each other.
+    pub spacing: f32,
+    /// Radians to turn the model about y so it faces forward (-z).
+    pub turn: f32,
+    /// Set for animals that go their own way rather than schooling.
+    pub behaviour: Option<BehaviourKind>,
+}
+
+/// The kinds of animal that go their own way, each with its own traits (see
+/// `behaviour`).
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BehaviourKind {
+    Turtle,
+    Ray,
+    ReefShark,
+}
+
+#[derive(Clone, Debug)]
+pub struct FaunaSelection {
+    pub species: Species,
+    pub weight: u32,
+    /// Depth below the surface it keeps to.
+    pub depth: Range<f32>,
+    /// Times of day it's about; wraps past midnight when `start > end`.
+    pub active: Range<f32>,
+    /// Animals per group (a school, or one on its own), both ends included.
+    pub group_size: RangeInclusive<u32>,
+}
+
+impl FaunaSelection {
+    pub fn active_at(&self, time_of_day: f32) -> bool {
+        if self.active.start <= self.active.end {
+            (self.active.start..=self.active.end).contains(&time_of_day)
+        } else {
+            time_of_day >= self.active.start || time_of_day <= self.active.end
+        }
+    }
+}
+
+/// The specks drifting in a region's water. See `marine_snow`.
+#[derive(Clone, Debug)]
+pub struct MarineSnow {
+    /// How much of the particle budget shows by day, 0..1.
+    pub density: f32,
+    /// The same at night, when the plankton come up.
+    pub night_density: f32,
+    pub colour: Color,
+    pub night_colour: Color,
+}
+
+impl Default for MarineSnow {
+    fn default() -> Self {
+        MarineSnow {
+            density: 0.4,
+            night_density: 0.7,
+            colour: Color::srgb(0.85, 0.9, 0.85),
+            night_colour: Color::srgb(0.3, 0.45, 0.6),
+        }
+    }
+}
+
 impl Region {
     pub fn new(
         name: String,
@@ -36,21 +150,18 @@ impl Region {
         objects: Vec<ObjectSelection>,
         lighting_setups: Vec<LightingSetup>,
     ) -> Region {
-        let mut objects_prefix = Vec::with_capacity(objects.len());
-        let mut objects_total_weight = 0;
-        for o in &objects {
-            objects_total_weight += o.selection_weight;
-            objects_prefix.push(objects_total_weight);
-        }
-
         Region {
             name,
             weight,
             height_sampler,
             objects,
-            objects_prefix,
-            objects_total_weight,
             lighting_setups,
+            density: 0.25,
+            caves: None,
+            floor: FloorSet::default(),
+            water_temperature: 28.0,
+            marine_snow: MarineSnow::default(),
+            fauna: vec![],
         }
     }
 }
@@ -60,37 +171,71 @@ impl Default for Region {
         Region {
             name: "".to_string(),
             weight: 0,
-            height_sampler: HeightNoise {
-                perlin_height: 0.0,
-                perlin_scale: 0.0,
-                perlin: Default::default(),
-                terrace_height: 0.0,
-                terrace_steps: 0.0,
-                terrace_scale: 0.0,
-                terrace_source: Default::default(),
-                terrace_smooth_width: 0.0,
-                offset: 0.0,
-            },
+            height_sampler: HeightNoise::Constant(0.0),
             objects: vec![],
-            objects_prefix: vec![],
-            objects_total_weight: 0,
             lighting_setups: vec![],
+            density: 0.0,
+            caves: None,
+            floor: FloorSet::default(),
+            water_temperature: 28.0,
+            marine_snow: MarineSnow::default(),
+            fauna: vec![],
         }
     }
 }
 
 impl Region {
-    pub fn pick_object(&self) -> String {
-        let mut rng = rand::rng();
-        let r = rng.random_range(0..self.objects_total_weight);
+    /// Weighted pick from the `fauna` that `allowed` accepts.
+    pub fn pick_fauna(
+        &self,
+        rng: &mut impl Rng,
+        allowed: impl Fn(&FaunaSelection) -> bool,
+    ) -> Option<&FaunaSelection> {
+        let allowed: Vec<&FaunaSelection> = self
+            .fauna
+            .iter()
+            .filter(|f| f.weight > 0 && allowed(f))
+            .collect();
+        let total: u32 = allowed.iter().map(|f| f.weight).sum();
+        if total == 0 {
+            return None;
+        }
 
-        let target = r + 1;
-        let id = self
-            .objects_prefix
-            .binary_search(&target)
-            .unwrap_or_else(|i| i);
+        let mut r = rng.random_range(0..total);
+        for f in allowed {
+            if r < f.weight {
+                return Some(f);
+            }
+            r -= f.weight;
+        }
+        None
+    }
+
+    /// Weighted pick from the `objects` that `allowed` accepts, drawing
+    /// from the caller's RNG so chunk generation stays reproducible.
+    pub fn pick_object(
+        &self,
+        rng: &mut impl Rng,
+        allowed: impl Fn(&ObjectSelection) -> bool,
+    ) -> Option<&ObjectSelection> {
+        let allowed: Vec<&ObjectSelection> = self
+            .objects
+            .iter()
+            .filter(|o| o.selection_weight > 0 && allowed(o))
+            .collect();
+        let total: u32 = allowed.iter().map(|o| o.selection_weight).sum();
+        if total == 0 {
+            return None;
+        }
 
-        self.objects[id].name.clone()
+        let mut r = rng.random_range(0..total);
+        for o in allowed {
+            if r < o.selection_weight {
+                return Some(o);
+            }
+            r -= o.selection_weight;
+        }
+        None
     }
 }
 
@@ -98,11 +243,13 @@ impl Region {
 pub struct ObjectSelection {
     pub name: String,
     pub selection_weight: u32,
+    /// Replaces the object's own placement rules in this region.
+    pub placement: Option<PlacementRules>,
 }
 
 /// A sampler that, given any world‐pos `p: Vec2`, returns
 /// `(near_id, far_id, blend)` exactly as described.
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct RegionSampler {
     pub regions: Vec<Region>,
     prefix: Vec<u32>,
@@ -117,7 +264,7 @@ impl RegionSampler {
     /// Build from a list of regions (with weights), plus your
     /// cell_size, jitter, blend width and seed.
     pub fn new(
-        mut regions: Vec<Region>,
+        regions: Vec<Region>,
         cell_size: f32,
         jitter: f32,
         blend_dist: f32,
@@ -165,25 +312,9 @@ impl RegionSampler {
                 let cell_x = cx + dx;
                 let cell_y = cy + dy;
 
-                // ==== JITTERED SITE POSITION ====
-                let h_j = self.hash(cell_x, cell_y, self.seed);
-                let fx = (h_j as f32 / u64::MAX as f32) * 2.0 - 1.0;
-                let fy = (((h_j >> 32) as f32 / u64::MAX as f32) * 2.0) - 1.0;
-                let site = Vec2::new(
-                    (cell_x as f32 + 0.5 + fx * self.jitter) * self.cell_size,
-                    (cell_y as f32 + 0.5 + fy * self.jitter) * self.cell_size,
-                );
-
+                let (site, region_id) = self.site(cell_x, cell_y);
                 let dist = (p - site).length_squared();
 
-                // ==== WEIGHTED REGION PICK ====
-                // use a second hash (tweak seed) for region choice
-                let h_r = self.hash(cell_x, cell_y, self.seed ^ 0x9E3779B97f4A7C15);
-                let r = (h_r % self.total_weight as u64) as u32;
-                // find first prefix > r  (i.e. bucket search)
-                let target = r + 1;
-                let region_id = self.prefix.binary_search(&target).unwrap_or_else(|i| i);
-
                 // ==== KEEP TWO NEAREST SITES ====
                 if dist < best1 {
                     best3 = best2;
@@ -242,6 +373,84 @@ impl RegionSampler {
         ([id1, id2, id3], [w1, w2, w3])
     }
 
+    /// The jittered Voronoi site of a grid cell and the region it belongs to.
+    fn site(&self, cell_x: i32, cell_y: i32) -> (Vec2, usize) {
+        // ==== JITTERED SITE POSITION ====
+        let h_j = self.hash(cell_x, cell_y, self.seed);
+        let fx = (h_j as f32 / u64::MAX as f32) * 2.0 - 1.0;
+        let fy = (((h_j >> 32) as f32 / u64::MAX as f32) * 2.0) - 1.0;
+        let site = Vec2::new(
+            (cell_x as f32 + 0.5 + fx * self.jitter) * self.cell_size,
+            (cell_y as f32 + 0.5 + fy * self.jitter) * self.cell_size,
+        );
+
+        // ==== WEIGHTED REGION PICK ====
+        // use a second hash (tweak seed) for region choice
+        let h_r = self.hash(cell_x, cell_y, self.seed ^ 0x9E3779B97F4A7C15);
+        let r = (h_r % self.total_weight as u64) as u32;
+        // find first prefix > r  (i.e. bucket search)
+        let target = r + 1;
+        let region_id = self.prefix.binary_search(&target).unwrap_or_else(|i| i);
+        (site, region_id)
+    }
+
+    /// Whether a region with caves blends in anywhere inside `min..max`.
+    /// Errs towards yes: it may flag a rectangle that only comes close to
+    /// a cave region, but never misses one that reaches in.
+    pub fn caves_in_rect(&self, min: Vec2, max: Vec2) -> bool {
+        if self.regions.iter().all(|r| r.caves.is_none()) {
+            return false;
+        }
+
+        // a region blends in where its site is less than blend_dist further
+        // away than the nearest one. That gap changes by at most 2 per unit
+        // moved, so samples `step` apart vouch for everything within
+        // step/√2 of them once the threshold is widened by √2·step
+        let step = (self.blend_dist * 0.5).clamp(0.25, self.cell_size * 0.5);
+        let margin = self.blend_dist + std::f32::consts::SQRT_2 * step;
+        let counts = ((max - min) / step).ceil().as_uvec2().max(UVec2::ONE);
+        (0..=counts.y).any(|iy| {
+            (0..=counts.x).any(|ix| {
+                let t = UVec2::new(ix, iy).as_vec2() / counts.as_vec2();
+                let p = min + (max - min) * t;
+                // 5×5 cells, so every site the 3×3 search could use
+                // anywhere near `p` is in view
+                let cx = (p.x / self.cell_size).floor() as i32;
+                let cy = (p.y / self.cell_size).floor() as i32;
+                let sites: Vec<(f32, usize)> = (-2..=2)
+                    .flat_map(|dy| (-2..=2).map(move |dx| (cx + dx, cy + dy)))
+                    .map(|(x, y)| {
+                        let (site, id) = self.site(x, y);
+                        (p.distance(site), id)
+                    })
+                    .collect();
+                let nearest = sites.iter().fold(f32::INFINITY, |m, &(d, _)| m.min(d));
+                sites
+                    .iter()
+                    .any(|&(d, id)| self.regions[id].caves.is_some() && d - nearest < margin)
+            })
+        })
+    }
+
+    /// Pick one of the regions blending at `p`, in proportion to its blend
+    /// weight. `roll` in [0, 1) decides which, so callers keep their own RNG.
+    pub fn pick_region(&self, p: Vec2, roll: f32) -> usize {
+        let (ids, weights) = self.sample_region(p);
+        let mut picked = ids[0];
+        let mut acc = 0.0;
+        for (id, w) in ids.into_iter().zip(weights) {
+            if w <= 0.0 {
+                continue;
+            }
+            picked = id;
+            acc += w;
+            if roll < acc {
+                break;
+            }
+        }
+        picked
+    }
+
     pub fn sample_surface_height(&self, p: Vec2) -> f64 {
         let ([id1, id2, id3], [w1, w2, w3]) = self.sample_region(p);
         let p_f64 = [(p.x) as f64, (p.y) as f64];
@@ -256,11 +465,12 @@ impl RegionSampler {
         height1 * w1 as f64 + height2 * w2 as f64 + height3 * w3 as f64
     }
 
-    pub fn sample_surface_orientation(
+    /// Surface normal at `p`, from central differences `eps` either side.
+    pub fn sample_surface_normal(
         &self,
         p: Vec2, // .x = X, .y = Z
         eps: f32,
-    ) -> (Quat, Vec3) {
+    ) -> Vec3 {
         // helper to sample and cast
         let sample = |dx: f32, dz: f32| self.sample_surface_height(p + Vec2::new(dx, dz)) as f32;
 
@@ -275,18 +485,372 @@ impl RegionSampler {
         let dh_dz = (hu - hd) / (2.0 * eps);
 
         // build the normal vector and normalize
-        let normal = Vec3::new(-dh_dx, 1.0, -dh_dz).normalize();
+        Vec3::new(-dh_dx, 1.0, -dh_dz).normalize()
+    }
+
+    pub fn sample_surface_orientation(
+        &self,
+        p: Vec2, // .x = X, .y = Z
+        eps: f32,
+    ) -> (Quat, Vec3) {
+        let normal = self.sample_surface_normal(p, eps);
 
         // rotate Y up → this normal
         (Quat::from_rotation_arc(Vec3::Y, normal), normal)
     }
 
+    /// Everything about the seabed at one x/z needed to answer density
+    /// queries up and down it. Cheaper than `sample_density` per point when
+    /// walking a whole column.
+    pub fn density_column(&self, p: Vec2) -> DensityColumn<'_> {
+        let (ids, weights) = self.sample_region(p);
+        let p_f64 = [p.x as f64, p.y as f64];
+        let height = ids
+            .iter()
+            .zip(weights)
+            .map(|(&id, w)| self.regions[id].height_sampler.sample(p_f64) * w as f64)
+            .sum::<f64>() as f32;
+        DensityColumn {
+            p,
+            height,
+            caves: [0, 1, 2].map(|i| (weights[i], self.regions[ids[i]].caves.as_ref())),
+        }
+    }
+
+    /// Solid rock is positive, open water negative, the seabed is zero.
+    /// Matches `sample_surface_height` wherever no region has caves.
+    pub fn sample_density(&self, p: Vec3) -> f32 {
+        self.density_column(Vec2::new(p.x, p.z)).density(p.y)
+    }
+
+    /// Unit normal of the density surface near `p`, pointing out of the rock.
+    pub fn sample_density_normal(&self, p: Vec3, eps: f32) -> Vec3 {
+        let sample = |d: Vec3| self.sample_density(p + d);
+        let gradient = Vec3::new(
+            sample(Vec3::X * eps) - sample(-Vec3::X * eps),
+            sample(Vec3::Y * eps) - sample(-Vec3::Y * eps),
+            sample(Vec3::Z * eps) - sample(-Vec3::Z * eps),
+        );
+        (-gradient).try_normalize().unwrap_or(Vec3::Y)
+    }
+
+    /// Height of the topmost rock surface at `p`, what you'd land on coming
+    /// straight down. Same as `sample_surface_height` outside cave regions.
+    pub fn sample_ground_height(&self, p: Vec2) -> f32 {
+        let column = self.density_column(p);
+        let reach = column.cave_reach();
+        if reach <= 0.0 {
+            return column.height;
+        }
+
+        // step down until we hit rock, then bisect for the surface
+        const STEP: f32 = 0.25;
+        let mut above = column.height + reach + STEP;
+        let bottom = column.height - reach - STEP;
+        while above > bottom {
+            let below = above - STEP;
+            if column.density(below) > 0.0 {
+                let (mut lo, mut hi) = (below, above);
+                for _ in 0..8 {
+                    let mid = (lo + hi) * 0.5;
+                    if column.density(mid) > 0.0 {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                return (lo + hi) * 0.5;
+            }
+            above = below;
+        }
+        bottom
+    }
+
+    /// Nudge `p` out of the rock until it has at least `clearance` of open
+    /// water around it. In plain heightfield regions this is just a clamp
+    /// to `clearance` above the surface. Never leaves `p` inside rock.
+    pub fn keep_clear(&self, p: Vec3, clearance: f32) -> Vec3 {
+        let column = self.density_column(Vec2::new(p.x, p.z));
+        if column.cave_reach() <= 0.0 {
+            return p.with_y(p.y.max(column.height + clearance));
+        }
+
+        // density falls off at roughly one per unit, so it doubles as a
+        // distance estimate for a few push-out steps
+        let mut p = p;
+        for _ in 0..4 {
+            let overlap = self.sample_density(p) + clearance;
+            if overlap <= 0.0 {
+                break;
+            }
+            p += self.sample_density_normal(p, 0.05) * overlap;
+        }
+        if self.sample_density(p) <= 0.0 {
+            return p;
+        }
+
+        // still in rock (a thin wall, or steps that overshot into more of
+        // it): come up out of the ground instead, or failing that out of
+        // the top of the column, where it's always open water
+        let xz = Vec2::new(p.x, p.z);
+        let above_ground = p.with_y(p.y.max(self.sample_ground_height(xz) + clearance));
+        if self.sample_density(above_ground) <= 0.0 {
+            return above_ground;
+        }
+        let column = self.density_column(xz);
+        p.with_y(column.height + column.cave_reach() + clearance.max(0.0))
+    }
+
+    /// What `chunk_rng` streams are seeded from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// A hash of everything here that decides the seabed and what's
+    /// scattered on it (not lighting, floors and the like), the same from
+    /// one run and one build to the next.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        hasher
+            .f32(self.cell_size)
+            .f32(self.jitter)
+            .f32(self.blend_dist)
+            .u64(self.regions.len() as u64);
+        for region in &self.regions {
+            hasher.str(&region.name).u32(region.weight);
+            region.height_sampler.hash_into(&mut hasher);
+            hasher.u64(region.objects.len() as u64);
+            for object in &region.objects {
+                hasher.str(&object.name).u32(object.selection_weight);
+                match &object.placement {
+                    Some(placement) => placement.hash_into(hasher.u32(1)),
+                    None => {
+                        hasher.u32(0);
+                    }
+                }
+            }
+            hasher.f32(region.density);
+            match &region.caves {
+                Some(caves) => caves.hash_into(hasher.u32(1)),
+                None => {
+                    hasher.u32(0);
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    /// The highest `density` of any region, used to thin scatter candidates.
+    pub fn max_density(&self) -> f32 {
+        self.regions.iter().fold(0.0, |m, r| m.max(r.density))
+    }
+
+    /// A deterministic RNG for everything generated inside chunk `coord`.
+    /// Same seed + same coord always gives the same stream, so a chunk
+    /// looks identical every time it is respawned. Separate `stream`s keep
+    /// independent passes (e.g. scatter vs. particles) from disturbing
+    /// each other. ChaCha rather than `StdRng`, whose output may change
+    /// between rand releases, so the world stays the same across versions.
+    pub fn chunk_rng(&self, coord: IVec2, stream: u64) -> ChaCha8Rng {
+        let seed = self.seed ^ 0xC2B2AE3D27D4EB4F ^ stream.wrapping_mul(0x9E3779B97F4A7C15);
+        ChaCha8Rng::seed_from_u64(self.hash(coord.x, coord.y, seed))
+    }
+
     /// A simple 2D→u64 mixer. You can swap in any small
     /// xorshift/SplitMix variant here.
     fn hash(&self, x: i32, y: i32, seed: u64) -> u64 {
-        let mut h = seed.wrapping_add(x as u64).wrapping_mul(0x9E3779B97f4A7C15);
+        let mut h = seed.wrapping_add(x as u64).wrapping_mul(0x9E3779B97F4A7C15);
         h = h.rotate_left(31) ^ y as u64;
-        h = h.wrapping_mul(0x9E3779B97f4A7C15);
+        h = h.wrapping_mul(0x9E3779B97F4A7C15);
         h.rotate_left(31)
     }
 }
+
+/// See `RegionSampler::density_column`.
+pub struct DensityColumn<'a> {
+    p: Vec2,
+    /// The blended heightfield here, before any caves.
+    pub height: f32,
+    caves: [(f32, Option<&'a CaveField>); 3],
+}
+
+impl DensityColumn<'_> {
+    /// How far above or below `height` rock can be found in this column;
+    /// zero means it is plain heightfield.
+    pub fn cave_reach(&self) -> f32 {
+        self.caves
+            .iter()
+            .filter_map(|(w, caves)| caves.map(|c| w * c.strength))
+            .sum()
+    }
+
+    pub fn density(&self, y: f32) -> f32 {
+        let point = [self.p.x as f64, y as f64, self.p.y as f64];
+        let caves: f64 = self
+            .caves
+            .iter()
+            .filter(|(w, _)| *w > 0.0)
+            .filter_map(|(w, caves)| caves.map(|c| c.sample(point) * *w as f64))
+            .sum();
+        self.height - y + caves as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noise::Perlin;
+
+    fn sampler(seed: u64) -> RegionSampler {
+        let flat = Region::new("Flat".into(), 1, HeightNoise::Constant(0.0), vec![], vec![]);
+        RegionSampler::new(vec![flat], 64.0, 0.5, 8.0, seed)
+    }
+
+    /// Rolling hills, and a second region that carves caves into them.
+    fn cave_sampler() -> RegionSampler {
+        let hills = |seed| HeightNoise::Perlin {
+            perlin: Perlin::new(seed),
+            scale: 0.05,
+            height: 3.0,
+        };
+        let plain = Region::new("Plain".into(), 1, hills(1), vec![], vec![]);
+        let mut caves = Region::new("Caves".into(), 1, hills(2), vec![], vec![]);
+        caves.caves = Some(CaveField {
+            perlin: Perlin::new(3),
+            scale: 0.2,
+            strength: 4.0,
+            octaves: 2,
+        });
+        RegionSampler::new(vec![plain, caves], 24.0, 0.5, 8.0, 5)
+    }
+
+    /// A spread of x/z points across several cells of `cave_sampler`.
+    fn points() -> impl Iterator<Item = Vec2> {
+        (0..40).flat_map(|i| (0..40).map(move |j| Vec2::new(i as f32 * 3.7 - 70.0, j as f32 * 3.3 - 60.0)))
+    }
+
+    fn draws(mut rng: ChaCha8Rng) -> Vec<u64> {
+        (0..8).map(|_| rng.random()).collect()
+    }
+
+    #[test]
+    fn chunk_rng_repeats_for_the_same_seed_and_coord() {
+        let coord = IVec2::new(-3, 11);
+        assert_eq!(
+            draws(sampler(42).chunk_rng(coord, 1)),
+            draws(sampler(42).chunk_rng(coord, 1))
+        );
+    }
+
+    /// Recorded from an earlier build: if these move, every saved world
+    /// generates differently.
+    #[test]
+    fn chunk_rng_stream_is_pinned() {
+        assert_eq!(
+            draws(sampler(42).chunk_rng(IVec2::new(-3, 11), 1))[..3],
+            [8281978982182678660, 258193098953121762, 15527762271096207952]
+        );
+    }
+
+    #[test]
+    fn chunk_rng_differs_by_stream_coord_and_seed() {
+        let coord = IVec2::new(-3, 11);
+        let base = draws(sampler(42).chunk_rng(coord, 1));
+        assert_ne!(base, draws(sampler(42).chunk_rng(coord, 2)));
+        assert_ne!(base, draws(sampler(42).chunk_rng(IVec2::new(-3, 12), 1)));
+        assert_ne!(base, draws(sampler(42).chunk_rng(IVec2::new(11, -3), 1)));
+        assert_ne!(base, draws(sampler(43).chunk_rng(coord, 1)));
+    }
+
+    #[test]
+    fn ground_height_is_surface_height_outside_caves() {
+        let sampler = cave_sampler();
+        let plain: Vec<Vec2> = points()
+            .filter(|&p| sampler.density_column(p).cave_reach() <= 0.0)
+            .collect();
+        assert!(!plain.is_empty());
+        for p in plain {
+            assert_eq!(sampler.sample_ground_height(p), sampler.sample_surface_height(p) as f32);
+        }
+    }
+
+    #[test]
+    fn keep_clear_never_leaves_a_point_in_rock() {
+        let sampler = cave_sampler();
+        assert!(points().any(|p| sampler.density_column(p).cave_reach() > 0.0));
+        for p in points() {
+            let ground = sampler.density_column(p).height;
+            for y in [-6.0, -3.0, -1.0, 0.0, 0.5, 2.0, 5.0] {
+                for clearance in [0.0, 0.3, 1.0] {
+                    let start = Vec3::new(p.x, ground + y, p.y);
+                    let clear = sampler.keep_clear(start, clearance);
+                    assert!(
+                        sampler.sample_density(clear) <= 0.0,
+                        "{start} came out at {clear}, still in rock"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Recorded from an earlier build: if this moves, every save is
+    /// dropped as belonging to another world.
+    #[test]
+    fn fingerprint_is_pinned() {
+        assert_eq!(cave_sampler().fingerprint(), 2776480468968561232);
+    }
+
+    #[test]
+    fn fingerprint_follows_what_shapes_the_world() {
+        let base = cave_sampler();
+        let mut deeper = cave_sampler();
+        deeper.regions[1].caves.as_mut().unwrap().strength = 5.0;
+        let mut denser = cave_sampler();
+        denser.regions[0].density = 1.0;
+        let mut relit = cave_sampler();
+        relit.regions[0].water_temperature = 30.0;
+        assert_ne!(base.fingerprint(), deeper.fingerprint());
+        assert_ne!(base.fingerprint(), denser.fingerprint());
+        assert_eq!(base.fingerprint(), relit.fingerprint());
+    }
+
+    fn fauna(active: Range<f32>) -> FaunaSelection {
+        FaunaSelection {
+            species: Species {
+                name: "Fish".into(),
+                model: "fish.glb".into(),
+                scale: 0.1,
+                speed: 1.0,
+                spacing: 0.5,
+                turn: 0.0,
+                behaviour: None,
+            },
+            weight: 1,
+            depth: 0.0..f32::INFINITY,
+            active,
+            group_size: 1..=1,
+        }
+    }
+
+    #[test]
+    fn active_at_includes_both_ends() {
+        let day = fauna(0.3..0.6);
+        for time in [0.3, 0.45, 0.6] {
+            assert!(day.active_at(time), "{time}");
+        }
+        for time in [0.0, 0.29, 0.61, 1.0] {
+            assert!(!day.active_at(time), "{time}");
+        }
+    }
+
+    #[test]
+    fn active_at_wraps_past_midnight() {
+        let night = fauna(0.8..0.1);
+        for time in [0.8, 0.9, 1.0, 0.0, 0.05, 0.1] {
+            assert!(night.active_at(time), "{time}");
+        }
+        for time in [0.11, 0.5, 0.79] {
+            assert!(!night.active_at(time), "{time}");
+        }
+    }
+}
@@ -0,0 +1,678 @@
+use crate::object_manager::{ObjectDefinition, ObjectManager, OrientationType};
+use crate::placement::PlacementRules;
+use crate::region_sampler::{ObjectSelection, RegionSampler};
+use bevy::math::IVec2;
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::sync::{Arc, Mutex};
+
+/// RNG stream for scatter candidates, see `RegionSampler::chunk_rng`.
+const SCATTER_STREAM: u64 = 1;
+
+/// Rough size of the patches `PlacementRules::clustering` gathers objects into.
+const CLUSTER_SIZE: f32 = 6.0;
+
+/// Sample spacing for the ground slope the placement rules see.
+const SLOPE_EPS: f32 = 0.5;
+
+/// An object the scatter pass decided to place, in chunk-local space.
+pub struct Placement {
+    pub name: String,
+    pub transform: Transform,
+    pub key: ScatterKey,
+}
+
+/// Which of its chunk's placements this is, in a way that holds from one
+/// run to the next for the same world: the candidate it came from (counting
+/// the ones thinned out, so it doesn't shift as others come and go) and the
+/// object picked there.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ScatterKey {
+    pub candidate: u32,
+    pub name: String,
+}
+
+/// A potential placement. Every chunk generates the same candidates no
+/// matter which chunk is asking, which is what keeps spacing consistent
+/// across chunk borders.
+struct Candidate {
+    name: String,
+    world_pos: Vec2,
+    radius: f32,
+    /// Minimum distance to another of the same object.
+    spacing: f32,
+    scale: f32,
+    yaw: f32,
+    /// Higher priority wins when two footprints overlap. Ends with the chunk
+    /// and the candidate's draw index there, which its `ScatterKey` keeps.
+    priority: (u64, i32, i32, usize),
+}
+
+/// Candidates of recently scattered chunks and their neighbours, so each
+/// chunk's are worked out once rather than again by every chunk within reach
+/// of it. Holds at most `capacity` chunks; past that the least recently
+/// used are dropped, a quarter of them at a time.
+/// Only good for the world it was filled from: make a new one when the
+/// regions or the catalogue change.
+pub struct CandidateCache {
+    capacity: usize,
+    entries: Mutex<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    /// Candidates per chunk, with when they were last asked for.
+    chunks: HashMap<IVec2, (Arc<[Candidate]>, u64)>,
+    clock: u64,
+}
+
+impl CandidateCache {
+    pub fn new(capacity: usize) -> Self {
+        CandidateCache {
+            capacity: capacity.max(1),
+            entries: Mutex::default(),
+        }
+    }
+
+    fn get_or_insert_with(
+        &self,
+        coord: IVec2,
+        make: impl FnOnce() -> Vec<Candidate>,
+    ) -> Arc<[Candidate]> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.clock += 1;
+            let now = entries.clock;
+            if let Some((candidates, used)) = entries.chunks.get_mut(&coord) {
+                *used = now;
+                return candidates.clone();
+            }
+        }
+
+        // worked out unlocked; two tasks racing for a chunk get the same answer
+        let candidates: Arc<[Candidate]> = make().into();
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let now = entries.clock;
+        entries.chunks.insert(coord, (candidates.clone(), now));
+        if entries.chunks.len() > self.capacity {
+            // drop down to three quarters full in one go rather than one
+            // chunk per insert; last-used times are unique, so exactly the
+            // oldest go (but always at least the one just made)
+            let keep = (self.capacity * 3 / 4).max(1);
+            let mut used: Vec<u64> = entries.chunks.values().map(|(_, used)| *used).collect();
+            let drop = used.len() - keep;
+            let (_, &mut oldest_kept, _) = used.select_nth_unstable(drop);
+            entries.chunks.retain(|_, (_, used)| *used >= oldest_kept);
+        }
+        candidates
+    }
+}
+
+/// Poisson-disk style scatter for one chunk.
+///
+/// Candidates are thrown uniformly at the highest region density, each picks
+/// a region by its blend weight at that spot, and is then thinned to that
+/// region's `density`. The object is picked from those whose placement rules
+/// accept the ground there. Overlaps are then settled in priority order, as
+/// if every candidate in the world were placed highest priority first: a
+/// candidate is kept unless a higher-priority candidate that is itself kept
+/// overlaps its footprint (or, for the same object, comes within its
+/// spacing). So no two placed objects overlap, a candidate that lost out
+/// can't knock out any others, and each chunk reaches the same verdict about
+/// its border without talking to the others. Region `density` is still an
+/// upper bound: where footprints are big next to the spacing it allows,
+/// fewer fit.
+///
+/// Checking a candidate takes the candidates of the (2·reach + 1)² chunks
+/// around it, where reach grows with the biggest footprint and spacing
+/// anything in the catalogue or the regions has, and checking whichever of
+/// those beat it can spread a little further. Each chunk's candidates sample
+/// heights and slopes; they come from `cache`, so chunks built near each
+/// other share the work. With a warm cache building a chunk costs about one
+/// chunk's worth of candidates, but a big spacing still makes a big ring to
+/// check.
+pub fn scatter_chunk(
+    coord: IVec2,
+    chunk_size: f32,
+    region_sampler: &RegionSampler,
+    object_manager: &ObjectManager,
+    cache: &CandidateCache,
+) -> Vec<Placement> {
+    let mut neighbourhood = Neighbourhood {
+        chunk_size,
+        reach: reach(chunk_size, region_sampler, object_manager),
+        region_sampler,
+        object_manager,
+        cache,
+        chunks: HashMap::new(),
+        kept: HashMap::new(),
+    };
+    let own = neighbourhood.candidates(coord);
+
+    let half = chunk_size * 0.5;
+    let chunk_center = coord.as_vec2() * chunk_size + Vec2::splat(half);
+
+    own.iter()
+        .enumerate()
+        .filter(|&(index, _)| neighbourhood.kept(coord, index))
+        .filter_map(|(_, c)| {
+            let def = &object_manager.get(&c.name)?.object_definition;
+            let transform = place_object(
+                def,
+                c.scale,
+                c.yaw,
+                c.world_pos - chunk_center,
+                chunk_center,
+                region_sampler,
+            );
+            Some(Placement {
+                name: c.name.clone(),
+                transform,
+                key: ScatterKey {
+                    candidate: c.priority.3 as u32,
+                    name: c.name.clone(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// How many chunks away a footprint conflicting with one in a chunk could
+/// live.
+fn reach(chunk_size: f32, region_sampler: &RegionSampler, object_manager: &ObjectManager) -> i32 {
+    let max_radius = object_manager
+        .objects
+        .values()
+        .map(|o| footprint_radius(&o.object_definition, o.object_definition.scale.end))
+        .fold(0.0, f32::max);
+    let max_spacing = object_manager
+        .objects
+        .values()
+        .map(|o| &o.object_definition.placement)
+        .chain(
+            region_sampler
+                .regions
+                .iter()
+                .flat_map(|r| &r.objects)
+                .filter_map(|o| o.placement.as_ref()),
+        )
+        .map(|rules| rules.spacing)
+        .fold(0.0, f32::max);
+    ((2.0 * max_radius).max(max_spacing) / chunk_size).ceil() as i32
+}
+
+/// The candidates one `scatter_chunk` call has looked at, and which of
+/// them it has settled are kept.
+struct Neighbourhood<'a> {
+    chunk_size: f32,
+    reach: i32,
+    region_sampler: &'a RegionSampler,
+    object_manager: &'a ObjectManager,
+    cache: &'a CandidateCache,
+    chunks: HashMap<IVec2, Arc<[Candidate]>>,
+    /// By chunk and index into its candidates.
+    kept: HashMap<(IVec2, usize), bool>,
+}
+
+impl Neighbourhood<'_> {
+    fn candidates(&mut self, coord: IVec2) -> Arc<[Candidate]> {
+        let (chunk_size, region_sampler, object_manager) =
+            (self.chunk_size, self.region_sampler, self.object_manager);
+        self.chunks
+            .entry(coord)
+            .or_insert_with(|| {
+                self.cache.get_or_insert_with(coord, || {
+                    chunk_candidates(coord, chunk_size, region_sampler, object_manager)
+                })
+            })
+            .clone()
+    }
+
+    /// Whether candidate `index` of chunk `coord` is kept: it is unless a
+    /// higher-priority candidate that's kept overlaps it. Each step goes to
+    /// a strictly higher priority, so this always bottoms out, and with
+    /// random priorities the chains are short.
+    fn kept(&mut self, coord: IVec2, index: usize) -> bool {
+        if let Some(&kept) = self.kept.get(&(coord, index)) {
+            return kept;
+        }
+        let candidates = self.candidates(coord);
+        let c = &candidates[index];
+
+        let mut kept = true;
+        'search: for dz in -self.reach..=self.reach {
+            for dx in -self.reach..=self.reach {
+                let other_coord = coord + IVec2::new(dx, dz);
+                let others = self.candidates(other_coord);
+                for (other_index, other) in others.iter().enumerate() {
+                    if beats(other, c) && self.kept(other_coord, other_index) {
+                        kept = false;
+                        break 'search;
+                    }
+                }
+            }
+        }
+        self.kept.insert((coord, index), kept);
+        kept
+    }
+}
+
+/// Whether `other` has priority over `c` and is too close for both to stay.
+fn beats(other: &Candidate, c: &Candidate) -> bool {
+    let mut clearance = c.radius + other.radius;
+    if c.name == other.name {
+        clearance = clearance.max(c.spacing.max(other.spacing));
+    }
+    other.priority > c.priority
+        && c.world_pos.distance_squared(other.world_pos) < clearance * clearance
+}
+
+fn chunk_candidates(
+    coord: IVec2,
+    chunk_size: f32,
+    region_sampler: &RegionSampler,
+    object_manager: &ObjectManager,
+) -> Vec<Candidate> {
+    let max_density = region_sampler.max_density();
+    if max_density <= 0.0 {
+        return vec![];
+    }
+
+    let mut rng = region_sampler.chunk_rng(coord, SCATTER_STREAM);
+    let origin = coord.as_vec2() * chunk_size;
+
+    // stochastic rounding so fractional counts average out
+    let expected = max_density * chunk_size * chunk_size;
+    let count = expected.floor() as usize + (rng.random::<f32>() < expected.fract()) as usize;
+
+    let mut candidates = Vec::with_capacity(count);
+    for index in 0..count {
+        // always draw the same numbers per candidate so later candidates
+        // don't shift when an earlier one is thinned out
+        let world_pos = origin
+            + Vec2::new(
+                rng.random_range(0.0..chunk_size),
+                rng.random_range(0.0..chunk_size),
+            );
+        let keep_roll: f32 = rng.random();
+        let region_roll: f32 = rng.random();
+        let pick_rng_seed: u64 = rng.random();
+        let priority: u64 = rng.random();
+
+        // pick by blend weight so object sets fade across region borders
+        // the same way heights do
+        let region = &region_sampler.regions[region_sampler.pick_region(world_pos, region_roll)];
+        if keep_roll >= region.density / max_density {
+            continue;
+        }
+
+        // what the ground is like here, for the placement rules
+        let height = region_sampler.sample_ground_height(world_pos);
+        let normal = region_sampler
+            .sample_density_normal(Vec3::new(world_pos.x, height, world_pos.y), SLOPE_EPS);
+        let slope = normal.y.clamp(-1.0, 1.0).acos().to_degrees();
+
+        let mut pick_rng = ChaCha8Rng::seed_from_u64(pick_rng_seed);
+        let Some(selection) = region.pick_object(&mut pick_rng, |o| {
+            placement_rules(o, object_manager).is_some_and(|rules| rules.allows(slope, height))
+        }) else {
+            continue;
+        };
+        let Some(obj) = object_manager.get(&selection.name) else {
+            continue;
+        };
+        let def = &obj.object_definition;
+        let rules = selection.placement.as_ref().unwrap_or(&def.placement);
+        let (scale, yaw) = draw_scale_yaw(def, &mut pick_rng);
+
+        if rules.clustering > 0.0
+            && pick_rng.random::<f32>() >= cluster_chance(&selection.name, world_pos, rules.clustering)
+        {
+            continue;
+        }
+
+        candidates.push(Candidate {
+            radius: footprint_radius(def, scale),
+            spacing: rules.spacing,
+            name: selection.name.clone(),
+            world_pos,
+            scale,
+            yaw,
+            priority: (priority, coord.x, coord.y, index),
+        });
+    }
+    candidates
+}
+
+/// The rules an object places by in this region: the region's override if
+/// it has one, else the object's own. `None` if it isn't in the catalogue.
+fn placement_rules<'a>(
+    selection: &'a ObjectSelection,
+    object_manager: &'a ObjectManager,
+) -> Option<&'a PlacementRules> {
+    let def = &object_manager.get(&selection.name)?.object_definition;
+    Some(selection.placement.as_ref().unwrap_or(&def.placement))
+}
+
+/// How likely an object with this `clustering` is kept at `p`. Patches come
+/// from smooth value noise seeded by the object's name, so each species
+/// gathers in its own places and every chunk agrees where those are.
+fn cluster_chance(name: &str, p: Vec2, clustering: f32) -> f32 {
+    // FNV-1a, stable across runs unlike the std hasher
+    let seed = name
+        .bytes()
+        .fold(0xCBF29CE484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001B3));
+    let lattice = |x: i32, z: i32| {
+        let mut h = seed
+            ^ (x as u64).wrapping_mul(0x9E3779B97F4A7C15)
+            ^ (z as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+        h ^= h >> 33;
+        h = h.wrapping_mul(0xFF51AFD7ED558CCD);
+        h ^= h >> 33;
+        (h >> 40) as f32 / (1u64 << 24) as f32
+    };
+
+    let q = p / CLUSTER_SIZE;
+    let cell = q.floor();
+    let t = q - cell;
+    let t = t * t * (Vec2::splat(3.0) - 2.0 * t);
+    let (x, z) = (cell.x as i32, cell.y as i32);
+    let noise = (lattice(x, z) * (1.0 - t.x) + lattice(x + 1, z) * t.x) * (1.0 - t.y)
+        + (lattice(x, z + 1) * (1.0 - t.x) + lattice(x + 1, z + 1) * t.x) * t.y;
+
+    // sharpen so patches have edges rather than a gentle gradient
+    let patch = ((noise - 0.5) * 3.0 + 0.5).clamp(0.0, 1.0);
+    1.0 - clustering * (1.0 - patch)
+}
+
+fn footprint_radius(def: &ObjectDefinition, scale: f32) -> f32 {
+    def.size.max_element() * scale
+}
+
+/// Scale drawn from the object's range, yaw according to its `OrientationType`.
+fn draw_scale_yaw(def: &ObjectDefinition, rng: &mut impl Rng) -> (f32, f32) {
+    let scale = if def.scale.start < def.scale.end {
+        rng.random_range(def.scale.clone())
+    } else {
+        def.scale.start
+    };
+
+    let yaw = match def.orientation_type {
+        OrientationType::HorizontalFree | OrientationType::VerticalForward => {
+            rng.random_range(-PI..PI)
+        }
+        OrientationType::Quarter => rng.random_range(0..4) as f32 * FRAC_PI_2,
+    };
+
+    (scale, yaw)
+}
+
+/// Work out where an object sits on the terrain: tilted to the footprint's
+/// slope (unless it stays upright) and sunk so no corner of the footprint
+/// floats. Whether the ground suits it is `PlacementRules`' call, made
+/// before this.
+fn place_object(
+    def: &ObjectDefinition,
+    scale: f32,
+    yaw: f32,
+    local_xz: Vec2,
+    chunk_center: Vec2,
+    region_sampler: &RegionSampler,
+) -> Transform {
+    let world_pos_2d = local_xz + chunk_center;
+    let size = def.size * scale;
+    let yaw_rot = Quat::from_rotation_y(yaw);
+
+    // the topmost rock, so objects land on ledges rather than inside them
+    let height = region_sampler.sample_ground_height(world_pos_2d);
+
+    let sample = |offset: Vec3| {
+        region_sampler.sample_ground_height(world_pos_2d + Vec2::new(offset.x, offset.z))
+    };
+
+    // footprint axes, turned by the same yaw the model gets
+    let v_right_2d = yaw_rot * (Vec3::X * size.x);
+    let v_up_2d = yaw_rot * (Vec3::Z * size.y);
+
+    let hl = sample(-v_right_2d);
+    let hr = sample(v_right_2d);
+    let hd = sample(-v_up_2d);
+    let hu = sample(v_up_2d);
+
+    // slope along each footprint axis, in the object's yawed frame
+    let ds_right = (hr - hl) / (2.0 * size.x);
+    let ds_up = (hu - hd) / (2.0 * size.y);
+    let normal = (yaw_rot * Vec3::new(-ds_right, 1.0, -ds_up)).normalize();
+
+    // rotate Y up → this normal, unless the model has to stand upright
+    let rot = match def.orientation_type {
+        OrientationType::VerticalForward => yaw_rot,
+        OrientationType::HorizontalFree | OrientationType::Quarter => {
+            Quat::from_rotation_arc(Vec3::Y, normal) * yaw_rot
+        }
+    };
+
+    // **** SINK PHASE ****
+
+    // Get the corners in 3D based on our actual orientation.
+    let v_right_3d = rot * (Vec3::X * size.x);
+    let v_up_3d = rot * (Vec3::Z * size.y);
+
+    // Find the distance offsets.
+    let d_l = hl + v_right_3d.y;
+    let d_r = hr - v_right_3d.y;
+    let d_d = hd + v_up_3d.y;
+    let d_u = hu - v_up_3d.y;
+
+    let y_pos_min = height.min(d_l).min(d_r).min(d_d).min(d_u);
+
+    Transform {
+        translation: Vec3::new(local_xz.x, y_pos_min, local_xz.y),
+        rotation: rot,
+        scale: Vec3::splat(scale),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region_sampler::Region;
+    use karang_lestari::height_noise::HeightNoise;
+
+    const CHUNK_SIZE: f32 = 2.0;
+
+    fn object(name: &str, size: f32, spacing: f32) -> ObjectDefinition {
+        ObjectDefinition {
+            name: name.into(),
+            path: format!("models/{name}.glb"),
+            orientation_type: OrientationType::HorizontalFree,
+            size: Vec2::splat(size),
+            scale: 0.8..1.2,
+            placement: PlacementRules {
+                spacing,
+                ..default()
+            },
+            coral: false,
+            restoration: None,
+            credits: vec![],
+        }
+    }
+
+    /// A flat, crowded reef: far more candidates than fit.
+    fn world() -> (RegionSampler, ObjectManager) {
+        let select = |name: &str, weight| ObjectSelection {
+            name: name.into(),
+            selection_weight: weight,
+            placement: None,
+        };
+        let mut reef = Region::new(
+            "Reef".into(),
+            1,
+            HeightNoise::Constant(0.0),
+            vec![select("Coral", 3), select("Rock", 1)],
+            vec![],
+        );
+        reef.density = 6.0;
+        let mut object_manager = ObjectManager::default();
+        object_manager.insert(object("Coral", 0.2, 0.0));
+        object_manager.insert(object("Rock", 0.35, 1.5));
+        (RegionSampler::new(vec![reef], 64.0, 0.5, 8.0, 7), object_manager)
+    }
+
+    fn scatter(coord: IVec2, world: &(RegionSampler, ObjectManager), cache: &CandidateCache) -> Vec<Placement> {
+        scatter_chunk(coord, CHUNK_SIZE, &world.0, &world.1, cache)
+    }
+
+    fn same(a: &[Placement], b: &[Placement]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|(a, b)| a.name == b.name && a.transform == b.transform)
+    }
+
+    #[test]
+    fn no_footprints_overlap_across_a_chunk_seam() {
+        let world = world();
+        let cache = CandidateCache::new(64);
+        let mut placed = Vec::new();
+        for coord in [IVec2::new(0, 0), IVec2::new(1, 0)] {
+            let placements = scatter(coord, &world, &cache);
+            assert!(!placements.is_empty());
+            let center = coord.as_vec2() * CHUNK_SIZE + Vec2::splat(CHUNK_SIZE * 0.5);
+            placed.extend(placements.into_iter().map(|p| {
+                let def = &world.1.get(&p.name).unwrap().object_definition;
+                let radius = footprint_radius(def, p.transform.scale.x);
+                (p.name, center + p.transform.translation.xz(), radius, def.placement.spacing)
+            }));
+        }
+
+        for (i, a) in placed.iter().enumerate() {
+            for b in &placed[i + 1..] {
+                let mut clearance = a.2 + b.2;
+                if a.0 == b.0 {
+                    clearance = clearance.max(a.3.max(b.3));
+                }
+                let distance = a.1.distance(b.1);
+                assert!(
+                    distance >= clearance - 1e-4,
+                    "{} at {} and {} at {} are {distance} apart, need {clearance}",
+                    a.0,
+                    a.1,
+                    b.0,
+                    b.1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn only_kept_candidates_knock_others_out() {
+        let world = world();
+        let cache = CandidateCache::new(64);
+        let coord = IVec2::new(3, -2);
+        let mut neighbourhood = Neighbourhood {
+            chunk_size: CHUNK_SIZE,
+            reach: reach(CHUNK_SIZE, &world.0, &world.1),
+            region_sampler: &world.0,
+            object_manager: &world.1,
+            cache: &cache,
+            chunks: HashMap::new(),
+            kept: HashMap::new(),
+        };
+        let own = neighbourhood.candidates(coord);
+        let mut dropped = 0;
+        for (index, c) in own.iter().enumerate() {
+            if neighbourhood.kept(coord, index) {
+                continue;
+            }
+            dropped += 1;
+            // whatever beat it was kept itself
+            let reach = neighbourhood.reach;
+            let beaten_by_kept = (-reach..=reach)
+                .flat_map(|dz| (-reach..=reach).map(move |dx| coord + IVec2::new(dx, dz)))
+                .any(|other_coord| {
+                    let others = neighbourhood.candidates(other_coord);
+                    others.iter().enumerate().any(|(other_index, other)| {
+                        beats(other, c) && neighbourhood.kept(other_coord, other_index)
+                    })
+                });
+            assert!(beaten_by_kept);
+        }
+        assert!(dropped > 0);
+    }
+
+    #[test]
+    fn cold_and_warm_cache_place_the_same() {
+        let world = world();
+        let coord = IVec2::new(-4, 5);
+        let cold = scatter(coord, &world, &CandidateCache::new(64));
+
+        // a small cache, warmed by nearby chunks and made to evict
+        let warm_cache = CandidateCache::new(8);
+        for dz in -3..=3 {
+            for dx in -3..=3 {
+                scatter(coord + IVec2::new(dx, dz), &world, &warm_cache);
+            }
+        }
+        let warm = scatter(coord, &world, &warm_cache);
+        assert!(!cold.is_empty());
+        assert!(same(&cold, &warm));
+    }
+
+    #[test]
+    fn same_world_and_coord_scatter_the_same() {
+        let coord = IVec2::new(2, -7);
+        let first = scatter(coord, &world(), &CandidateCache::new(64));
+        let again = scatter(coord, &world(), &CandidateCache::new(64));
+        assert!(!first.is_empty());
+        assert!(same(&first, &again));
+
+        let elsewhere = scatter(coord + IVec2::X, &world(), &CandidateCache::new(64));
+        assert!(!same(&first, &elsewhere));
+    }
+
+    #[test]
+    fn cache_keeps_the_most_recently_used() {
+        let cache = CandidateCache::new(4);
+        let made = std::cell::Cell::new(0);
+        let get = |x| {
+            cache.get_or_insert_with(IVec2::new(x, 0), || {
+                made.set(made.get() + 1);
+                vec![]
+            });
+        };
+        for x in 0..4 {
+            get(x);
+        }
+        // 0 is used again, so 1 is now the oldest
+        get(0);
+        get(4);
+        assert_eq!(made.get(), 5);
+        get(0);
+        get(4);
+        assert_eq!(made.get(), 5);
+        get(1);
+        assert_eq!(made.get(), 6);
+
+        // with room for one, only the latest is kept
+        let cache = CandidateCache::new(1);
+        let made = std::cell::Cell::new(0);
+        let get = |x| {
+            cache.get_or_insert_with(IVec2::new(x, 0), || {
+                made.set(made.get() + 1);
+                vec![]
+            });
+        };
+        get(0);
+        get(1);
+        get(1);
+        assert_eq!(made.get(), 2);
+        get(0);
+        assert_eq!(made.get(), 3);
+    }
+}
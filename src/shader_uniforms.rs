@@ -0,0 +1,36 @@
+//! Uniform blocks the game's materials hand their shaders.
+//!
+//! `ShaderType`'s derive asserts each field's layout with a `check`
+//! function that's never called, which rustc reports as dead code. Nothing
+//! on the struct reaches those functions, so the uniforms are kept here
+//! where one `expect` covers them all.
+#![expect(dead_code)]
+
+use bevy::prelude::*;
+use bevy::render::render_resource::ShaderType;
+
+/// Per weight slot (see `floor_material::splat_attributes`): which texture
+/// layers to use and how to tint and tile them.
+#[derive(ShaderType, Reflect, Debug, Clone, Default)]
+pub struct FloorSplatUniform {
+    pub flat_layer: UVec4,
+    pub steep_layer: UVec4,
+    pub flat_tint: [Vec4; 4],
+    pub steep_tint: [Vec4; 4],
+    pub flat_scale: Vec4,
+    pub steep_scale: Vec4,
+    pub steep_start: f32,
+    pub steep_end: f32,
+}
+
+/// Colour, drift and speck size for `marine_snow`'s shader.
+#[derive(ShaderType, Debug, Clone, Default)]
+pub struct MarineSnowUniform {
+    /// Linear colour, with the opacity of a speck's centre in alpha.
+    pub colour: Vec4,
+    pub drift: Vec3,
+    pub volume: f32,
+    /// Share of the specks showing, 0..1.
+    pub density: f32,
+    pub size: f32,
+}
@@ -0,0 +1,43 @@
+/// FNV-1a, fed one field at a time. Unlike `DefaultHasher` (or hashing
+/// `Debug` output) what goes in is spelled out here, little-endian, so a
+/// hash is the same from one run and one build to the next and can go in
+/// a save file. Whatever is fed to it needs to write a tag or length ahead
+/// of anything variable, so different values can't run together.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        StableHasher(0xcbf29ce484222325)
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+        self
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    pub(crate) fn f32(&mut self, value: f32) -> &mut Self {
+        self.u32(value.to_bits())
+    }
+
+    pub(crate) fn f64(&mut self, value: f64) -> &mut Self {
+        self.u64(value.to_bits())
+    }
+
+    pub(crate) fn str(&mut self, value: &str) -> &mut Self {
+        self.u64(value.len() as u64).bytes(value.as_bytes())
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -1,8 +1,6 @@
-use bevy::input::gamepad::{Gamepad, GamepadAxis, GamepadButton};
-
 use bevy::prelude::*;
+use crate::camera::components::FollowTarget;
 use crate::fishy::FishMovement;
-use crate::height_noise::HeightNoise;
 use crate::smooth_math::smooth_damp_f32;
 
 static ANIMATION_GRAPH_PATH: &str = "animation_graphs/turtle_animations.animgraph.ron";
@@ -28,7 +26,7 @@ impl Plugin for TurtlePlugin {
 }
 
 #[derive(Component)]
-struct TurtleAnimation {
+pub struct TurtleAnimation {
     swim_weight_current: f32,
     swim_weight_velocity: f32,
 
@@ -39,8 +37,7 @@ pub struct TurtleAnimationGraph(Handle<AnimationGraph>);
 
 pub fn load_animation(
     mut commands: Commands,
-    mut asset_server: ResMut<AssetServer>,
-    mut animation_graphs: ResMut<Assets<AnimationGraph>>,
+    asset_server: ResMut<AssetServer>,
 ){
     commands.insert_resource(TurtleAnimationGraph(
         asset_server.load(ANIMATION_GRAPH_PATH),
@@ -71,12 +68,12 @@ pub fn init_animations(
 }
 
 pub fn turtle_animation_system(
-    mut movement_query: Query<(&mut FishMovement)>,
+    movement_query: Query<&FishMovement, With<FollowTarget>>,
     mut anim_query: Query<(&mut AnimationPlayer, &mut TurtleAnimation)>,
     time: Res<Time>,
 ) {
     for (mut animation_player, mut turtle_anim) in anim_query.iter_mut() {
-        for ( mover) in movement_query.iter() {
+        for mover in movement_query.iter() {
 
             let swim_weight = if mover.current_go_force > 0.0 { 1.0 } else { 0.0 };
             let swim_speed = mover.current_go_force;
@@ -0,0 +1,536 @@
+use crate::chunked_env::ChunkSettings;
+use crate::object_manager::ObjectManager;
+use crate::region_sampler::RegionSampler;
+use crate::scatter::ScatterKey;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Remembers what's been changed in each chunk (objects added, removed or
+/// moved) so the changes come back whenever the chunk respawns, and keeps
+/// them in a save file between runs.
+///
+/// Call `.add_plugins(WorldEditsPlugin::default())` in your App.
+pub struct WorldEditsPlugin {
+    /// Where edits are saved. Ignored on the web, where they only last the
+    /// session.
+    pub path: PathBuf,
+    /// How often to write out unsaved edits (they're also saved on exit).
+    pub save_every: Duration,
+}
+
+impl Default for WorldEditsPlugin {
+    fn default() -> Self {
+        WorldEditsPlugin {
+            path: "saves/world_edits.ron".into(),
+            save_every: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Plugin for WorldEditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WorldEdits {
+            path: self.path.clone(),
+            ..default()
+        })
+        .insert_resource(WorldEditsSaveTimer(Timer::new(
+            self.save_every,
+            TimerMode::Repeating,
+        )))
+        .add_systems(Startup, load_world_edits)
+        .add_systems(
+            Update,
+            (
+                check_world_fingerprint.run_if(
+                    resource_exists::<RegionSampler>
+                        .and(|manager: Res<ObjectManager>| manager.catalogue_ready),
+                ),
+                save_world_edits_periodically,
+            ),
+        )
+        .add_systems(Last, save_world_edits_on_exit);
+    }
+}
+
+/// Which object in a chunk an entity is: one of the chunk's scattered
+/// objects, or one added by hand. Put on every object `spawn_chunk` makes,
+/// so whatever changes it can record the change.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkObject {
+    pub coord: IVec2,
+    pub id: ChunkObjectId,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ChunkObjectId {
+    /// One of the chunk's scatter placements, which come out the same every
+    /// time for the same world.
+    Scattered(ScatterKey),
+    Added(u32),
+}
+
+/// Which world a set of edits was made in. Scattered objects are only
+/// generated the same for the same seed, regions and catalogue, so edits to
+/// them mean nothing in any other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldFingerprint {
+    seed: u64,
+    regions: u64,
+    catalogue: u64,
+}
+
+impl WorldFingerprint {
+    pub fn of(region_sampler: &RegionSampler, object_manager: &ObjectManager) -> Self {
+        WorldFingerprint {
+            seed: region_sampler.seed(),
+            regions: region_sampler.fingerprint(),
+            catalogue: object_manager.fingerprint(),
+        }
+    }
+}
+
+/// An object placed by hand, in chunk-local space.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AddedObject {
+    pub name: String,
+    pub transform: SavedTransform,
+    /// For a planted coral fragment, the in-game day it went in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planted_day: Option<f64>,
+}
+
+/// `Transform` in a form the save file can hold.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SavedTransform {
+    translation: [f32; 3],
+    rotation: [f32; 4],
+    scale: [f32; 3],
+}
+
+impl From<Transform> for SavedTransform {
+    fn from(t: Transform) -> Self {
+        SavedTransform {
+            translation: t.translation.to_array(),
+            rotation: t.rotation.to_array(),
+            scale: t.scale.to_array(),
+        }
+    }
+}
+
+impl From<SavedTransform> for Transform {
+    fn from(t: SavedTransform) -> Self {
+        Transform {
+            translation: Vec3::from_array(t.translation),
+            rotation: Quat::from_array(t.rotation),
+            scale: Vec3::from_array(t.scale),
+        }
+    }
+}
+
+/// Everything that differs in one chunk from what generation gives it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ChunkDiff {
+    /// Scattered objects taken away.
+    #[serde(default)]
+    removed: BTreeSet<ScatterKey>,
+    /// Scattered objects in a new spot, chunk-local.
+    #[serde(default)]
+    moved: BTreeMap<ScatterKey, SavedTransform>,
+    #[serde(default)]
+    added: BTreeMap<u32, AddedObject>,
+    #[serde(default)]
+    next_added: u32,
+}
+
+impl ChunkDiff {
+    /// Where scattered object `key` should be, or `None` if it's gone.
+    pub fn scattered(&self, key: &ScatterKey, generated: Transform) -> Option<Transform> {
+        if self.removed.contains(key) {
+            return None;
+        }
+        Some(self.moved.get(key).map_or(generated, |&t| t.into()))
+    }
+
+    /// Hand-placed objects, with their ids.
+    pub fn added(&self) -> impl Iterator<Item = (u32, &AddedObject)> {
+        self.added.iter().map(|(&id, object)| (id, object))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.moved.is_empty() && self.added.is_empty()
+    }
+}
+
+/// The per-chunk diff layer. Record a change here as well as making it to
+/// the live entities; `spawn_chunk` only reads it when a chunk (or its
+/// objects) next spawns.
+#[derive(Resource, Default)]
+pub struct WorldEdits {
+    chunks: BTreeMap<(i32, i32), ChunkDiff>,
+    /// Chunk size the diffs were recorded at; coords mean nothing at another.
+    chunk_size: f32,
+    /// The world the diffs were recorded in; `None` until it's been built,
+    /// and nothing is saved before then.
+    world: Option<WorldFingerprint>,
+    /// The in-game clock, so things that grow pick up where they left off.
+    world_days: Option<f64>,
+    path: PathBuf,
+    dirty: bool,
+}
+
+impl WorldEdits {
+    pub fn chunk(&self, coord: IVec2) -> Option<&ChunkDiff> {
+        self.chunks.get(&(coord.x, coord.y))
+    }
+
+    fn chunk_mut(&mut self, coord: IVec2) -> &mut ChunkDiff {
+        self.dirty = true;
+        self.chunks.entry((coord.x, coord.y)).or_default()
+    }
+
+    /// Record an object added to chunk `coord` (its transform chunk-local).
+    /// Tag its entity with the returned `ChunkObject`.
+    pub fn add(&mut self, coord: IVec2, object: AddedObject) -> ChunkObject {
+        let diff = self.chunk_mut(coord);
+        let id = diff.next_added;
+        diff.next_added += 1;
+        diff.added.insert(id, object);
+        ChunkObject {
+            coord,
+            id: ChunkObjectId::Added(id),
+        }
+    }
+
+    /// Record an object being taken away.
+    #[allow(dead_code, reason = "for whatever removes objects; nothing in the game does yet")]
+    pub fn remove(&mut self, object: ChunkObject) {
+        let diff = self.chunk_mut(object.coord);
+        match object.id {
+            ChunkObjectId::Scattered(key) => {
+                diff.moved.remove(&key);
+                diff.removed.insert(key);
+            }
+            ChunkObjectId::Added(id) => {
+                diff.added.remove(&id);
+            }
+        }
+        if diff.is_empty() {
+            self.chunks.remove(&(object.coord.x, object.coord.y));
+        }
+    }
+
+    /// Record an object now sitting at `transform` (chunk-local).
+    #[allow(dead_code, reason = "for whatever moves objects; nothing in the game does yet")]
+    pub fn set_transform(&mut self, object: ChunkObject, transform: Transform) {
+        let diff = self.chunk_mut(object.coord);
+        match object.id {
+            ChunkObjectId::Scattered(key) => {
+                diff.moved.insert(key, transform.into());
+            }
+            ChunkObjectId::Added(id) => {
+                if let Some(added) = diff.added.get_mut(&id) {
+                    added.transform = transform.into();
+                }
+            }
+        }
+    }
+
+    /// In-game days gone by as of the save that was loaded, if there was one.
+    pub fn world_days(&self) -> Option<f64> {
+        self.world_days
+    }
+
+    /// Keep the in-game clock for the next save. Doesn't make a save due on
+    /// its own; it goes out with the next edit (or on exit).
+    pub fn set_world_days(&mut self, days: f64) {
+        self.world_days = Some(days);
+    }
+
+    /// Take note of the world the edits are for, first dropping any made in
+    /// a different one (from an old save, or before a hot reload), as its
+    /// scattered objects won't be where they were or what they were. Keeps
+    /// the clock, which has already been picked up by then.
+    fn set_world(&mut self, world: WorldFingerprint) {
+        if self.world.is_some_and(|w| w != world) && !self.chunks.is_empty() {
+            warn!(
+                "Ignoring world edits in {}: they were made with a different seed, regions or objects",
+                self.path.display()
+            );
+            self.chunks.clear();
+        }
+        self.world = Some(world);
+    }
+
+    /// Write the edits out now, if there's anything new.
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // nothing can be edited before the world's built, and a save
+            // that doesn't say which world it's for couldn't be checked
+            let Some(world) = self.world else {
+                return;
+            };
+            let file = SaveFile {
+                chunk_size: self.chunk_size,
+                world,
+                world_days: self.world_days,
+                chunks: self.chunks.clone(),
+            };
+            let result = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+                .map_err(|e| e.to_string())
+                .and_then(|text| {
+                    if let Some(dir) = self.path.parent() {
+                        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                    }
+                    // write then rename, so a crash mid-save can't leave half a file
+                    let partial = self.path.with_extension("ron.partial");
+                    std::fs::write(&partial, text).map_err(|e| e.to_string())?;
+                    std::fs::rename(&partial, &self.path).map_err(|e| e.to_string())
+                });
+            match result {
+                Ok(()) => info!("Saved world edits to {}", self.path.display()),
+                Err(err) => warn!("Couldn't save world edits to {}: {err}", self.path.display()),
+            }
+        }
+    }
+
+    /// Read in the save file, if there is one made at the same chunk size.
+    /// The world it was made in is checked later, once that's been built
+    /// (see `check_world_fingerprint`).
+    fn load(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let text = match std::fs::read_to_string(&self.path) {
+                Ok(text) => text,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return,
+                Err(err) => {
+                    warn!("Couldn't read world edits from {}: {err}", self.path.display());
+                    return;
+                }
+            };
+            match ron::de::from_str::<SaveFile>(&text) {
+                Ok(file) if file.chunk_size != self.chunk_size => warn!(
+                    "Ignoring world edits in {}: they were made with {} unit chunks, not {}",
+                    self.path.display(),
+                    file.chunk_size,
+                    self.chunk_size
+                ),
+                Ok(file) => {
+                    info!(
+                        "Loaded world edits for {} chunks from {}",
+                        file.chunks.len(),
+                        self.path.display()
+                    );
+                    self.chunks = file.chunks;
+                    self.world = Some(file.world);
+                    self.world_days = file.world_days;
+                }
+                Err(err) => warn!("Ignoring world edits in {}: {err}", self.path.display()),
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    chunk_size: f32,
+    world: WorldFingerprint,
+    #[serde(default)]
+    world_days: Option<f64>,
+    chunks: BTreeMap<(i32, i32), ChunkDiff>,
+}
+
+#[derive(Resource)]
+struct WorldEditsSaveTimer(Timer);
+
+pub fn load_world_edits(mut edits: ResMut<WorldEdits>, settings: Res<ChunkSettings>) {
+    edits.chunk_size = settings.chunk_size;
+    edits.load();
+}
+
+/// Checks the edits belong to the world as built, whenever the regions or
+/// the catalogue change. Runs before chunks spawn, so none picks up edits
+/// from another world.
+pub fn check_world_fingerprint(
+    region_sampler: Res<RegionSampler>,
+    object_manager: Res<ObjectManager>,
+    mut edits: ResMut<WorldEdits>,
+) {
+    if region_sampler.is_changed() || object_manager.is_changed() {
+        edits.set_world(WorldFingerprint::of(&region_sampler, &object_manager));
+    }
+}
+
+fn save_world_edits_periodically(
+    time: Res<Time>,
+    mut timer: ResMut<WorldEditsSaveTimer>,
+    mut edits: ResMut<WorldEdits>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        edits.save();
+    }
+}
+
+fn save_world_edits_on_exit(mut exit: EventReader<AppExit>, mut edits: ResMut<WorldEdits>) {
+    if exit.read().next().is_some() {
+        // the clock has moved on even if nothing else has
+        edits.dirty |= !edits.chunks.is_empty();
+        edits.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object_manager::{ObjectDefinition, OrientationType};
+    use crate::placement::PlacementRules;
+    use crate::region_sampler::{ObjectSelection, Region};
+    use crate::scatter::{self, CandidateCache};
+    use karang_lestari::height_noise::HeightNoise;
+
+    const CHUNK_SIZE: f32 = 4.0;
+    const COORD: IVec2 = IVec2::new(2, -1);
+
+    fn world(seed: u64) -> (RegionSampler, ObjectManager) {
+        let object = |name: &str| ObjectDefinition {
+            name: name.into(),
+            path: format!("models/{name}.glb"),
+            orientation_type: OrientationType::HorizontalFree,
+            size: Vec2::splat(0.3),
+            scale: 0.8..1.2,
+            placement: PlacementRules::default(),
+            coral: false,
+            restoration: None,
+            credits: vec![],
+        };
+        let select = |name: &str| ObjectSelection {
+            name: name.into(),
+            selection_weight: 1,
+            placement: None,
+        };
+        let mut reef = Region::new(
+            "Reef".into(),
+            1,
+            HeightNoise::Constant(0.0),
+            vec![select("Coral"), select("Rock")],
+            vec![],
+        );
+        reef.density = 2.0;
+        let mut object_manager = ObjectManager::default();
+        object_manager.insert(object("Coral"));
+        object_manager.insert(object("Rock"));
+        (RegionSampler::new(vec![reef], 64.0, 0.5, 8.0, seed), object_manager)
+    }
+
+    fn fingerprint(world: &(RegionSampler, ObjectManager)) -> WorldFingerprint {
+        WorldFingerprint::of(&world.0, &world.1)
+    }
+
+    fn edits(test: &str, world: WorldFingerprint) -> WorldEdits {
+        WorldEdits {
+            path: std::env::temp_dir().join(format!("karang_lestari_{test}_{}.ron", std::process::id())),
+            chunk_size: CHUNK_SIZE,
+            world: Some(world),
+            ..default()
+        }
+    }
+
+    /// Save, then load into fresh edits the way the next run would.
+    fn reload(edits: &mut WorldEdits, world: WorldFingerprint) -> WorldEdits {
+        edits.save();
+        let mut loaded = WorldEdits {
+            path: edits.path.clone(),
+            chunk_size: CHUNK_SIZE,
+            ..default()
+        };
+        loaded.load();
+        loaded.set_world(world);
+        std::fs::remove_file(&edits.path).unwrap();
+        loaded
+    }
+
+    /// The scattered objects spawning `COORD` would show, with `edits` applied.
+    fn respawn(world: &(RegionSampler, ObjectManager), edits: &WorldEdits) -> Vec<(String, Transform)> {
+        let cache = CandidateCache::new(64);
+        scatter::scatter_chunk(COORD, CHUNK_SIZE, &world.0, &world.1, &cache)
+            .into_iter()
+            .filter_map(|p| {
+                let transform = match edits.chunk(COORD) {
+                    Some(diff) => diff.scattered(&p.key, p.transform)?,
+                    None => p.transform,
+                };
+                Some((p.name, transform))
+            })
+            .collect()
+    }
+
+    fn scattered(world: &(RegionSampler, ObjectManager), nth: usize) -> ChunkObject {
+        let cache = CandidateCache::new(64);
+        let placements = scatter::scatter_chunk(COORD, CHUNK_SIZE, &world.0, &world.1, &cache);
+        ChunkObject {
+            coord: COORD,
+            id: ChunkObjectId::Scattered(placements[nth].key.clone()),
+        }
+    }
+
+    #[test]
+    fn edits_survive_a_save_and_load() {
+        let world = world(7);
+        let mut edits = edits("round_trip", fingerprint(&world));
+        edits.remove(scattered(&world, 0));
+        edits.set_transform(scattered(&world, 1), Transform::from_xyz(0.25, 1.5, -0.75));
+        edits.add(
+            COORD,
+            AddedObject {
+                name: "Coral".into(),
+                transform: Transform::from_xyz(1.0, 0.0, 1.0).into(),
+                planted_day: Some(2.5),
+            },
+        );
+        edits.set_world_days(3.5);
+
+        let loaded = reload(&mut edits, fingerprint(&world));
+        assert_eq!(respawn(&world, &loaded), respawn(&world, &edits));
+        let added: Vec<_> = loaded.chunk(COORD).unwrap().added().collect();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].1.name, "Coral");
+        assert_eq!(Transform::from(added[0].1.transform), Transform::from_xyz(1.0, 0.0, 1.0));
+        assert_eq!(added[0].1.planted_day, Some(2.5));
+        assert_eq!(loaded.world_days(), Some(3.5));
+    }
+
+    #[test]
+    fn removed_scattered_object_stays_removed_after_respawn() {
+        let world = world(7);
+        let generated = respawn(&world, &WorldEdits::default());
+        assert!(generated.len() > 2);
+
+        let mut edits = edits("removed", fingerprint(&world));
+        edits.remove(scattered(&world, 1));
+        let loaded = reload(&mut edits, fingerprint(&world));
+
+        let mut expected = generated;
+        expected.remove(1);
+        assert_eq!(respawn(&world, &loaded), expected);
+    }
+
+    #[test]
+    fn edits_from_another_world_are_dropped() {
+        let world = world(7);
+        let mut edits = edits("other_world", fingerprint(&world));
+        edits.remove(scattered(&world, 0));
+        edits.set_world_days(3.5);
+
+        let loaded = reload(&mut edits, fingerprint(&self::world(8)));
+        assert!(loaded.chunk(COORD).is_none());
+        assert_eq!(loaded.world_days(), Some(3.5));
+    }
+}